@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+
+/// The money left to spend on a level, which goes negative when the player overspends
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget(pub i32);
+
+impl Budget {
+    pub fn charge(&mut self, cost: i32) {
+        self.0 -= cost;
+    }
+
+    pub fn refund(&mut self, cost: i32) {
+        self.0 += cost;
+    }
+
+    pub fn is_overdrawn(&self) -> bool {
+        self.0 < 0
+    }
+
+    /// Whether paying the cost would leave the budget at zero or above, anything that pays money back always can be
+    pub fn can_afford(&self, cost: i32) -> bool {
+        cost <= 0 || cost <= self.0
+    }
+
+    /// How far below zero the budget is
+    pub fn overdraft(&self) -> i32 {
+        (-self.0).max(0)
+    }
+}
+
+
+/// What happens when the player tries to place equipment they can't afford, set for each level
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetMode {
+    /// Placement is blocked
+    #[default]
+    HardCap,
+    /// Placement goes ahead and the budget goes negative, costing points at the end of the level
+    Overdraft,
+}
+
+
+/// The points a finished level is worth. A win scores the money and whole seconds left over, and every unit the
+/// budget is overdrawn by takes the penalty off whether the level was won or not.
+pub fn level_score(won: bool, budget: Budget, time_left: f32, overdraft_penalty: i32) -> i32 {
+    let reward = if won {
+        budget.0.max(0) + time_left as i32
+    } else {
+        0
+    };
+    reward - budget.overdraft() * overdraft_penalty
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_goes_back_to_where_it_was_after_a_refund() {
+        let mut budget = Budget(10);
+
+        budget.charge(12);
+        assert!(budget.is_overdrawn());
+        budget.refund(12);
+
+        assert_eq!(budget, Budget(10));
+    }
+
+    #[test]
+    fn overdraft_costs_points_even_on_a_win() {
+        assert!(Budget(3).can_afford(3));
+        assert!(!Budget(3).can_afford(4));
+        assert!(Budget(-2).can_afford(-1));
+
+        assert_eq!(level_score(true, Budget(4), 10.5, 5), 14);
+        assert_eq!(level_score(true, Budget(-2), 10.5, 5), 0);
+        assert_eq!(level_score(false, Budget(-2), 10.5, 5), -10);
+    }
+}
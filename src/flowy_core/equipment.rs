@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+use super::settings::{GameSettings, AssetSettings, AssetMeta, HydraulicSettings};
+use super::grid::{Cell, Direction};
+use super::level::{Terrain, Feature};
+use super::budget::{Budget, BudgetMode};
+
+
+/// The type of a piece of equipment, matching an entry in the `[assets]` settings table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EquipmentKind {
+    StraightPipe,
+    Bend,
+    Tee,
+    Quad,
+    Pump,
+    PumpStation,
+    Home,
+    Business,
+}
+
+
+/// Clockwise quarter turns away from the unrotated layout of a piece
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Rotation {
+    #[default]
+    Zero,
+    Quarter,
+    Half,
+    ThreeQuarter,
+}
+
+
+impl EquipmentKind {
+    pub const ALL: [EquipmentKind; 8] = [
+        EquipmentKind::StraightPipe,
+        EquipmentKind::Bend,
+        EquipmentKind::Tee,
+        EquipmentKind::Quad,
+        EquipmentKind::Pump,
+        EquipmentKind::PumpStation,
+        EquipmentKind::Home,
+        EquipmentKind::Business,
+    ];
+
+    /// Whether the player can place this kind of equipment, as opposed to it being part of the level
+    pub fn is_placeable(&self) -> bool {
+        !matches!(self, EquipmentKind::PumpStation | EquipmentKind::Home | EquipmentKind::Business)
+    }
+
+    /// Whether this is a pipe fitting, which is turned to join the pipes around it
+    pub fn is_fitting(&self) -> bool {
+        matches!(self, EquipmentKind::StraightPipe | EquipmentKind::Bend | EquipmentKind::Tee | EquipmentKind::Quad)
+    }
+
+    /// Pick the pipe fitting and rotation that opens on exactly the given sides.
+    /// A single side is treated as a straight run through the cell and no sides gives an unrotated straight pipe.
+    pub fn fitting_for(sides: &[Direction]) -> (EquipmentKind, Rotation) {
+        let mut ports = Vec::with_capacity(sides.len());
+        for side in sides {
+            if !ports.contains(side) {
+                ports.push(*side);
+            }
+        }
+        if let [port] = ports[..] {
+            ports.push(port.opposite());
+        }
+        if ports.is_empty() {
+            return (EquipmentKind::StraightPipe, Rotation::Zero);
+        }
+
+        for kind in [EquipmentKind::StraightPipe, EquipmentKind::Bend, EquipmentKind::Tee, EquipmentKind::Quad] {
+            for rotation in Rotation::ALL {
+                let fitting_ports = kind.open_ports(rotation);
+                if fitting_ports.len() == ports.len() && ports.iter().all(|port| fitting_ports.contains(port)) {
+                    return (kind, rotation);
+                }
+            }
+        }
+
+        (EquipmentKind::Quad, Rotation::Zero)
+    }
+
+    /// The selected fitting when it is laid on its own, e.g. a single click, otherwise the fitting from `fitting_for`
+    /// so a drawn path never lays a fitting with ports left open to leak
+    pub fn fitting_with(selected: EquipmentKind, sides: &[Direction]) -> (EquipmentKind, Rotation) {
+        if selected.is_fitting() && sides.is_empty() {
+            return (selected, Rotation::Zero);
+        }
+
+        EquipmentKind::fitting_for(sides)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EquipmentKind::StraightPipe => "Straight Pipe",
+            EquipmentKind::Bend => "Bend",
+            EquipmentKind::Tee => "Tee",
+            EquipmentKind::Quad => "Quad",
+            EquipmentKind::Pump => "Pump",
+            EquipmentKind::PumpStation => "Pump Station",
+            EquipmentKind::Home => "Home",
+            EquipmentKind::Business => "Business",
+        }
+    }
+
+    pub fn asset_meta<'a>(&self, assets: &'a AssetSettings) -> &'a AssetMeta {
+        match self {
+            EquipmentKind::StraightPipe => &assets.straight_pipe,
+            EquipmentKind::Bend => &assets.bend,
+            EquipmentKind::Tee => &assets.tee,
+            EquipmentKind::Quad => &assets.quad,
+            EquipmentKind::Pump => &assets.pump,
+            EquipmentKind::PumpStation => &assets.pump_station,
+            EquipmentKind::Home => &assets.home,
+            EquipmentKind::Business => &assets.business,
+        }
+    }
+
+    /// The open ports of the unrotated piece
+    fn base_ports(&self) -> &'static [Direction] {
+        match self {
+            EquipmentKind::StraightPipe => &[Direction::North, Direction::South],
+            EquipmentKind::Bend => &[Direction::North, Direction::East],
+            EquipmentKind::Tee => &[Direction::East, Direction::South, Direction::West],
+            EquipmentKind::Quad => &Direction::ALL,
+            EquipmentKind::Pump => &[Direction::North, Direction::South],
+            EquipmentKind::PumpStation => &Direction::ALL,
+            EquipmentKind::Home => &Direction::ALL,
+            EquipmentKind::Business => &Direction::ALL,
+        }
+    }
+
+    /// The sides of the cell that water can flow in and out of once the piece has been rotated
+    pub fn open_ports(&self, rotation: Rotation) -> Vec<Direction> {
+        self.base_ports()
+            .iter()
+            .map(|direction| rotation.apply(*direction))
+            .collect()
+    }
+}
+
+
+impl Rotation {
+    pub const ALL: [Rotation; 4] = [Rotation::Zero, Rotation::Quarter, Rotation::Half, Rotation::ThreeQuarter];
+
+    fn quarter_turns(&self) -> usize {
+        match self {
+            Rotation::Zero => 0,
+            Rotation::Quarter => 1,
+            Rotation::Half => 2,
+            Rotation::ThreeQuarter => 3,
+        }
+    }
+
+    /// The angle to turn the sprite of an unrotated piece by, clockwise is negative in world space
+    pub fn angle(&self) -> f32 {
+        -(self.quarter_turns() as f32) * std::f32::consts::FRAC_PI_2
+    }
+
+    /// Rotate a side of the unrotated piece to where it ends up
+    pub fn apply(&self, direction: Direction) -> Direction {
+        (0..self.quarter_turns()).fold(direction, |direction, _| direction.clockwise())
+    }
+}
+
+
+/// The cost of placing a piece of equipment on a cell, the base cost of the asset scaled by the terrain
+pub fn placement_cost(kind: EquipmentKind, terrain: Terrain, game_settings: &GameSettings) -> i32 {
+    let base_cost = kind.asset_meta(&game_settings.assets).cost;
+    (base_cost as f32 * terrain.settings(&game_settings.terrain).cost_multiplier).round() as i32
+}
+
+
+/// Whether a new piece could be laid in a cell, or the first reason it can't
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Placeable,
+    Occupied,
+    ForbiddenTerrain,
+    Unaffordable,
+}
+
+impl Placement {
+    /// Check a cell for a piece costing the given amount. Not being able to afford it only stops a hard capped budget.
+    pub fn check(cell: &Cell, cost: i32, budget: Budget, budget_mode: BudgetMode, game_settings: &GameSettings) -> Self {
+        if cell.occupied || cell.feature.is_some() {
+            Placement::Occupied
+        } else if !cell.terrain.settings(&game_settings.terrain).placeable {
+            Placement::ForbiddenTerrain
+        } else if budget_mode == BudgetMode::HardCap && !budget.can_afford(cost) {
+            Placement::Unaffordable
+        } else {
+            Placement::Placeable
+        }
+    }
+
+    pub fn is_placeable(&self) -> bool {
+        *self == Placement::Placeable
+    }
+}
+
+
+/// What a pump station, home or business placed by the level puts in to or takes out of the network,
+/// in litres per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FixtureMeta {
+    /// A pump station and the most water it can supply
+    Source { capacity: f32 },
+    /// A home or business and the water it draws
+    Consumer { demand: f32 },
+}
+
+impl FixtureMeta {
+    /// The equipment and metadata for a level feature, blocked cells aren't fixtures
+    pub fn for_feature(feature: Feature, settings: &HydraulicSettings) -> Option<(EquipmentKind, Self)> {
+        match feature {
+            Feature::Blocked => None,
+            Feature::PumpStation => Some((EquipmentKind::PumpStation, FixtureMeta::Source { capacity: settings.station_capacity })),
+            Feature::Home => Some((EquipmentKind::Home, FixtureMeta::Consumer { demand: settings.home_demand })),
+            Feature::Business => Some((EquipmentKind::Business, FixtureMeta::Consumer { demand: settings.business_demand })),
+        }
+    }
+
+    pub fn capacity(&self) -> f32 {
+        match self {
+            FixtureMeta::Source { capacity } => *capacity,
+            FixtureMeta::Consumer { .. } => 0.0,
+        }
+    }
+
+    pub fn demand(&self) -> f32 {
+        match self {
+            FixtureMeta::Source { .. } => 0.0,
+            FixtureMeta::Consumer { demand } => *demand,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowy_core::grid::CellId;
+    use crate::flowy_core::level::Level;
+    use crate::flowy_core::test_support::{LEVEL, build_grid};
+
+    #[test]
+    fn selected_fitting_is_only_laid_on_its_own() {
+        assert_eq!(EquipmentKind::fitting_with(EquipmentKind::Tee, &[]), (EquipmentKind::Tee, Rotation::Zero));
+        // along a drawn path a tee or quad would leave ports open, so the fitting that joins the path is laid
+        assert_eq!(
+            EquipmentKind::fitting_with(EquipmentKind::Tee, &[Direction::West, Direction::East]),
+            (EquipmentKind::StraightPipe, Rotation::Quarter)
+        );
+        assert_eq!(
+            EquipmentKind::fitting_with(EquipmentKind::Quad, &[Direction::West]),
+            EquipmentKind::fitting_for(&[Direction::West])
+        );
+        assert_eq!(
+            EquipmentKind::fitting_with(EquipmentKind::Bend, &[Direction::North, Direction::South]),
+            (EquipmentKind::StraightPipe, Rotation::Zero)
+        );
+        assert_eq!(
+            EquipmentKind::fitting_with(EquipmentKind::StraightPipe, &[Direction::North, Direction::East]),
+            EquipmentKind::fitting_for(&[Direction::North, Direction::East])
+        );
+    }
+
+    #[test]
+    fn placement_is_checked_against_the_cell_and_the_budget() {
+        let game_settings = GameSettings::built_in();
+        let grid = build_grid(&Level::parse("test", LEVEL, 10).unwrap());
+        let check = |column, budget, budget_mode| {
+            Placement::check(&grid.cells[&CellId::new(column, 0)], 1, Budget(budget), budget_mode, &game_settings)
+        };
+
+        assert_eq!(check(0, 10, BudgetMode::HardCap), Placement::Occupied);
+        assert_eq!(check(1, 10, BudgetMode::HardCap), Placement::Placeable);
+        assert_eq!(check(1, 0, BudgetMode::HardCap), Placement::Unaffordable);
+        assert_eq!(check(1, 0, BudgetMode::Overdraft), Placement::Placeable);
+    }
+}
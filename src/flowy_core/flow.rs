@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::grid::{Grid, Cell, CellId, Direction};
+use super::level::Feature;
+use super::equipment::{EquipmentKind, Rotation};
+
+
+/// Which parts of the grid water can reach from the pump stations
+#[derive(Default, Debug)]
+pub struct FlowNetwork {
+    /// Every cell that water reaches, including the pump stations themselves
+    pub wet_cells: HashSet<CellId>,
+    /// Homes and businesses that have a supply
+    pub connected_consumers: HashSet<CellId>,
+    /// Homes and businesses without a supply
+    pub disconnected_consumers: HashSet<CellId>,
+    /// Wet equipment that doesn't lead to any consumer
+    pub dead_ends: HashSet<CellId>,
+    /// Open ports on wet equipment that aren't joined to anything, as the cell and the side water spills out of
+    pub leaks: Vec<(CellId, Direction)>,
+}
+
+impl FlowNetwork {
+    pub fn all_consumers_connected(&self) -> bool {
+        self.disconnected_consumers.is_empty()
+    }
+}
+
+
+/// Walk the grid from every pump station through connected equipment to find where the water goes.
+/// Two cells are connected when both have an open port facing each other. Pump stations, homes and businesses
+/// accept a connection on every side, but water doesn't flow through a home or business to reach anything else.
+pub fn solve_flow(grid: &Grid, equipment_ports: &HashMap<CellId, Vec<Direction>>) -> FlowNetwork {
+    let mut flow_network = FlowNetwork::default();
+    let mut queue = VecDeque::new();
+
+    for cell in grid.cells.values() {
+        match cell.feature {
+            Some(Feature::PumpStation) => {
+                flow_network.wet_cells.insert(cell.id);
+                queue.push_back(cell.id);
+            },
+            Some(Feature::Home) | Some(Feature::Business) => {
+                flow_network.disconnected_consumers.insert(cell.id);
+            },
+            _ => {},
+        }
+    }
+
+    while let Some(cell_idx) = queue.pop_front() {
+        let cell = &grid.cells[&cell_idx];
+        if is_consumer(cell) {
+            continue;
+        }
+        for (_, neighbour_idx) in connections(grid, cell, equipment_ports) {
+            if flow_network.wet_cells.insert(neighbour_idx) {
+                queue.push_back(neighbour_idx);
+            }
+        }
+    }
+
+    flow_network.connected_consumers = flow_network.disconnected_consumers
+        .iter()
+        .filter(|cell_idx| flow_network.wet_cells.contains(cell_idx))
+        .copied()
+        .collect();
+    flow_network.disconnected_consumers.retain(|cell_idx| !flow_network.connected_consumers.contains(cell_idx));
+
+    // any open port on wet equipment that isn't joined to a neighbour is spilling water
+    for cell_idx in flow_network.wet_cells.iter() {
+        let cell = &grid.cells[cell_idx];
+        if cell.feature.is_some() {
+            continue;
+        }
+        let connected_sides = connections(grid, cell, equipment_ports)
+            .into_iter()
+            .map(|(direction, _)| direction)
+            .collect::<Vec<Direction>>();
+        for direction in open_ports(cell, equipment_ports) {
+            if !connected_sides.contains(direction) {
+                flow_network.leaks.push((cell.id, *direction));
+            }
+        }
+    }
+
+    flow_network.dead_ends = find_dead_ends(grid, equipment_ports, &flow_network.wet_cells);
+    flow_network
+}
+
+
+/// Repeatedly trim wet equipment with at most one connection, as water can only go in and never come out.
+/// Whatever gets trimmed doesn't lie on a path between a pump station and a consumer.
+fn find_dead_ends(grid: &Grid, equipment_ports: &HashMap<CellId, Vec<Direction>>, wet_cells: &HashSet<CellId>) -> HashSet<CellId> {
+    let mut degrees = wet_cells
+        .iter()
+        .map(|cell_idx| (*cell_idx, connections(grid, &grid.cells[cell_idx], equipment_ports).len()))
+        .collect::<HashMap<CellId, usize>>();
+
+    let mut dead_ends = HashSet::new();
+    let mut queue = degrees
+        .iter()
+        .filter(|(cell_idx, degree)| **degree <= 1 && grid.cells[*cell_idx].feature.is_none())
+        .map(|(cell_idx, _)| *cell_idx)
+        .collect::<VecDeque<CellId>>();
+
+    while let Some(cell_idx) = queue.pop_front() {
+        if !dead_ends.insert(cell_idx) {
+            continue;
+        }
+        for (_, neighbour_idx) in connections(grid, &grid.cells[&cell_idx], equipment_ports) {
+            if dead_ends.contains(&neighbour_idx) || grid.cells[&neighbour_idx].feature.is_some() {
+                continue;
+            }
+            if let Some(degree) = degrees.get_mut(&neighbour_idx) {
+                *degree = degree.saturating_sub(1);
+                if *degree <= 1 {
+                    queue.push_back(neighbour_idx);
+                }
+            }
+        }
+    }
+
+    dead_ends
+}
+
+
+pub fn is_consumer(cell: &Cell) -> bool {
+    matches!(cell.feature, Some(Feature::Home) | Some(Feature::Business))
+}
+
+
+fn open_ports<'a>(cell: &Cell, equipment_ports: &'a HashMap<CellId, Vec<Direction>>) -> &'a [Direction] {
+    match cell.feature {
+        Some(Feature::Blocked) => &[],
+        Some(_) => &Direction::ALL,
+        None => equipment_ports
+            .get(&cell.id)
+            .map(|ports| ports.as_slice())
+            .unwrap_or(&[]),
+    }
+}
+
+
+/// The neighbours a cell is joined to, along with the side they are joined on
+pub fn connections(grid: &Grid, cell: &Cell, equipment_ports: &HashMap<CellId, Vec<Direction>>) -> Vec<(Direction, CellId)> {
+    open_ports(cell, equipment_ports)
+        .iter()
+        .filter_map(|direction| {
+            let neighbour = grid.cells.get(&cell.neighbours.get(*direction)?)?;
+            open_ports(neighbour, equipment_ports)
+                .contains(&direction.opposite())
+                .then_some((*direction, neighbour.id))
+        })
+        .collect()
+}
+
+
+/// The open ports of each piece of equipment keyed by the cell it sits in
+pub fn equipment_ports(equipment: impl Iterator<Item = (CellId, EquipmentKind, Rotation)>) -> HashMap<CellId, Vec<Direction>> {
+    equipment
+        .map(|(cell_idx, kind, rotation)| (cell_idx, kind.open_ports(rotation)))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowy_core::level::Level;
+    use crate::flowy_core::test_support::{LEVEL, build_grid};
+
+    #[test]
+    fn home_without_a_pipe_is_disconnected() {
+        let level = Level::parse("test", LEVEL, 10).unwrap();
+        let grid = build_grid(&level);
+
+        let flow_network = solve_flow(&grid, &HashMap::new());
+
+        assert!(flow_network.connected_consumers.is_empty());
+        assert!(flow_network.disconnected_consumers.contains(&CellId::new(2, 0)));
+    }
+}
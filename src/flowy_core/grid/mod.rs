@@ -0,0 +1,224 @@
+mod store;
+
+use super::level::{Level, Terrain, Feature};
+pub use store::CellStore;
+
+
+/// A stable identity for a cell made from its column and row, counting from the top left.
+/// The same level always produces the same ids, so they can be saved and compared between runs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellId {
+    pub column: i32,
+    pub row: i32,
+}
+
+impl CellId {
+    pub fn new(column: i32, row: i32) -> Self {
+        Self {
+            column,
+            row,
+        }
+    }
+
+    /// The id of the cell on the given side of this one, which may be off the grid
+    pub fn step(&self, direction: Direction) -> Self {
+        match direction {
+            Direction::North => CellId::new(self.column, self.row - 1),
+            Direction::East => CellId::new(self.column + 1, self.row),
+            Direction::South => CellId::new(self.column, self.row + 1),
+            Direction::West => CellId::new(self.column - 1, self.row),
+        }
+    }
+}
+
+
+#[derive(Debug, Eq, PartialEq, Hash, Default, Clone, Copy)]
+pub struct CellCentre {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl CellCentre {
+    fn new(x: i32, y: i32) -> Self {
+        Self {
+            x,
+            y,
+        }
+    }
+}
+
+
+#[derive(Default, Debug)]
+pub struct GridSettings {
+    pub cell_width: i32,
+    pub cell_height: i32,
+    pub grid_width: i32,
+    pub grid_height: i32,
+    pub number_of_horizontal_cells: i32,
+    pub number_of_vertical_cells: i32,
+}
+
+impl GridSettings {
+    /// The size of the grid for a level laid out in cells of the given size
+    pub fn for_level(cell_width: i32, cell_height: i32, level: &Level) -> Self {
+        Self {
+            cell_width,
+            cell_height,
+            grid_width: level.columns * cell_width,
+            grid_height: level.rows * cell_height,
+            number_of_horizontal_cells: level.columns,
+            number_of_vertical_cells: level.rows,
+        }
+    }
+
+    /// The cell under a point measured in pixels from the top left of the grid, worked out directly from the cell size.
+    /// Points off the edge of the grid aren't in any cell.
+    pub fn cell_at(&self, x: i32, y: i32) -> Option<CellId> {
+        if self.cell_width <= 0 || self.cell_height <= 0 || x < 0 || y < 0 || x >= self.grid_width || y >= self.grid_height {
+            return None;
+        }
+        Some(CellId::new(x / self.cell_width, y / self.cell_height))
+    }
+}
+
+
+/// Used to find cell information from cursor cell id
+#[derive(Default, Debug)]
+pub struct Grid {
+    pub cells: CellStore
+}
+
+impl Grid {
+    /// An empty grid, storing its cells in chunks if a chunk size is given
+    pub fn new(chunk_size: Option<i32>) -> Self {
+        Self {
+            cells: CellStore::new(chunk_size),
+        }
+    }
+
+    /// Every cell of a level in one go, joined to its neighbours
+    pub fn build(grid_settings: &GridSettings, level: &Level, chunk_size: Option<i32>) -> Self {
+        let mut grid = Grid::new(chunk_size);
+        for row in 0..grid_settings.number_of_vertical_cells {
+            grid.build_row(grid_settings, level, row);
+        }
+        grid.fill_neighbours();
+        grid
+    }
+
+    /// Add every cell in a row of the level, the cells aren't joined up until `fill_neighbours` is called
+    pub fn build_row(&mut self, grid_settings: &GridSettings, level: &Level, row: i32) {
+        for column in 0..grid_settings.number_of_horizontal_cells {
+            let left = column * grid_settings.cell_width;
+            let right = left + grid_settings.cell_width;
+            let top = row * grid_settings.cell_height;
+            let bottom = top + grid_settings.cell_height;
+
+            let cell_id = CellId::new(column, row);
+            let tile = level
+                .tile(column, row)
+                .copied()
+                .unwrap_or_default();
+
+            self.cells.insert(
+                cell_id,
+                Cell {
+                    id: cell_id,
+                    centre: CellCentre::new(left + (grid_settings.cell_width / 2), top + (grid_settings.cell_height / 2)),
+                    bounds: (left, right, top, bottom),
+                    occupied: tile.feature.is_some(),
+                    occupied_by: None,
+                    terrain: tile.terrain,
+                    feature: tile.feature,
+                    neighbours: CellNeighbours::default()
+                }
+            );
+        }
+    }
+
+    pub fn fill_neighbours(&mut self) {
+        let cell_ids = self.cells.ids();
+
+        for (_, cell) in self.cells.iter_mut() {
+            // cells off the edge of the map have no neighbour
+            let neighbour = |direction: Direction| Some(cell.id.step(direction)).filter(|id| cell_ids.contains(id));
+
+            cell.neighbours.left = neighbour(Direction::West);
+            cell.neighbours.right = neighbour(Direction::East);
+            cell.neighbours.top = neighbour(Direction::North);
+            cell.neighbours.bottom = neighbour(Direction::South);
+        }
+    }
+}
+
+
+#[derive(Default, Debug)]
+pub struct Cell {
+    pub id: CellId,
+    /// The x,y position of the cell in the grid
+    pub centre: CellCentre,
+    /// The bounds of the cell (Left, Right, Top, Bottom)
+    pub bounds: (i32, i32, i32, i32),
+    /// Whether the cell is occupied by an entity
+    pub occupied: bool,
+    /// The entity the level placed in this cell, if any, kept as its bits so the rules don't depend on the engine
+    pub occupied_by: Option<u64>,
+    /// The ground the cell is built on
+    pub terrain: Terrain,
+    /// The pre-placed level feature in this cell, if any
+    pub feature: Option<Feature>,
+    /// The ids of the neighbour cells
+    pub neighbours: CellNeighbours
+}
+
+
+#[derive(Debug, Default, Hash)]
+pub struct CellNeighbours {
+    pub left: Option<CellId>,
+    pub right: Option<CellId>,
+    pub top: Option<CellId>,
+    pub bottom: Option<CellId>
+}
+
+impl CellNeighbours {
+    pub fn get(&self, direction: Direction) -> Option<CellId> {
+        match direction {
+            Direction::North => self.top,
+            Direction::East => self.right,
+            Direction::South => self.bottom,
+            Direction::West => self.left,
+        }
+    }
+}
+
+
+/// The four sides of a cell, with north being the top of the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    pub fn clockwise(&self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+}
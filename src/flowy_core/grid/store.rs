@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Index;
+
+use super::{Cell, CellId};
+
+
+/// The cells of the grid keyed by their id.
+///
+/// Small maps keep every cell in a single map. Maps far bigger than the window can be split into square chunks of
+/// cells instead, so each chunk is a dense block that is indexed directly and only chunks holding cells are allocated.
+#[derive(Debug)]
+pub enum CellStore {
+    Flat(HashMap<CellId, Cell>),
+    Chunked {
+        /// Number of cells along each side of a chunk
+        chunk_size: i32,
+        chunks: HashMap<(i32, i32), Vec<Option<Cell>>>,
+    },
+}
+
+impl Default for CellStore {
+    fn default() -> Self {
+        CellStore::Flat(HashMap::new())
+    }
+}
+
+
+impl CellStore {
+    /// An empty store, chunked if a chunk size is given
+    pub fn new(chunk_size: Option<i32>) -> Self {
+        match chunk_size {
+            Some(chunk_size) if chunk_size > 0 => CellStore::Chunked { chunk_size, chunks: HashMap::new() },
+            _ => CellStore::default(),
+        }
+    }
+
+    /// The chunk a cell falls in and its slot within that chunk
+    fn locate(chunk_size: i32, cell_id: &CellId) -> ((i32, i32), usize) {
+        let chunk = (cell_id.column.div_euclid(chunk_size), cell_id.row.div_euclid(chunk_size));
+        let slot = cell_id.row.rem_euclid(chunk_size) * chunk_size + cell_id.column.rem_euclid(chunk_size);
+        (chunk, slot as usize)
+    }
+
+    pub fn get(&self, cell_id: &CellId) -> Option<&Cell> {
+        match self {
+            CellStore::Flat(cells) => cells.get(cell_id),
+            CellStore::Chunked { chunk_size, chunks } => {
+                let (chunk, slot) = Self::locate(*chunk_size, cell_id);
+                chunks.get(&chunk)?[slot].as_ref()
+            },
+        }
+    }
+
+    pub fn get_mut(&mut self, cell_id: &CellId) -> Option<&mut Cell> {
+        match self {
+            CellStore::Flat(cells) => cells.get_mut(cell_id),
+            CellStore::Chunked { chunk_size, chunks } => {
+                let (chunk, slot) = Self::locate(*chunk_size, cell_id);
+                chunks.get_mut(&chunk)?[slot].as_mut()
+            },
+        }
+    }
+
+    pub fn insert(&mut self, cell_id: CellId, cell: Cell) {
+        match self {
+            CellStore::Flat(cells) => {
+                cells.insert(cell_id, cell);
+            },
+            CellStore::Chunked { chunk_size, chunks } => {
+                let (chunk, slot) = Self::locate(*chunk_size, &cell_id);
+                let slots = (*chunk_size * *chunk_size) as usize;
+                chunks.entry(chunk).or_insert_with(|| (0..slots).map(|_| None).collect())[slot] = Some(cell);
+            },
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            CellStore::Flat(cells) => cells.clear(),
+            CellStore::Chunked { chunks, .. } => chunks.clear(),
+        }
+    }
+
+    pub fn values(&self) -> Box<dyn Iterator<Item = &Cell> + '_> {
+        match self {
+            CellStore::Flat(cells) => Box::new(cells.values()),
+            CellStore::Chunked { chunks, .. } => Box::new(chunks.values().flatten().flatten()),
+        }
+    }
+
+    pub fn values_mut(&mut self) -> Box<dyn Iterator<Item = &mut Cell> + '_> {
+        match self {
+            CellStore::Flat(cells) => Box::new(cells.values_mut()),
+            CellStore::Chunked { chunks, .. } => Box::new(chunks.values_mut().flatten().flatten()),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (CellId, &mut Cell)> {
+        self.values_mut().map(|cell| (cell.id, cell))
+    }
+
+    /// The ids of every cell in the store
+    pub fn ids(&self) -> HashSet<CellId> {
+        self.values().map(|cell| cell.id).collect()
+    }
+}
+
+
+impl Index<&CellId> for CellStore {
+    type Output = Cell;
+
+    fn index(&self, cell_id: &CellId) -> &Self::Output {
+        // same as a HashMap, asking for a cell that isn't on the grid is a bug
+        self.get(cell_id).expect("no cell with this id on the grid")
+    }
+}
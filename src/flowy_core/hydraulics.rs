@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry};
+
+use super::settings::{GameSettings, PipeSize};
+use super::grid::{Grid, CellId, Direction};
+use super::level::Feature;
+use super::equipment::{EquipmentKind, FixtureMeta};
+use super::flow::{connections, is_consumer};
+
+
+/// The steady state of the water network
+#[derive(Default, Debug)]
+pub struct HydraulicState {
+    /// Pressure and flow in every cell that water reaches
+    pub cells: HashMap<CellId, CellHydraulics>,
+    /// Homes and businesses getting their demand at or above the minimum pressure
+    pub supplied_consumers: HashSet<CellId>,
+    /// Homes and businesses that are disconnected, below the minimum pressure or fed by an overloaded station
+    pub starved_consumers: HashSet<CellId>,
+    /// Pump stations asked for more water than they can supply
+    pub overloaded_stations: HashSet<CellId>,
+}
+
+impl HydraulicState {
+    pub fn all_consumers_supplied(&self) -> bool {
+        self.starved_consumers.is_empty()
+    }
+}
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CellHydraulics {
+    /// Pressure head in metres of water
+    pub pressure: f32,
+    /// Flow through the cell in litres per second
+    pub flow: f32,
+}
+
+
+/// Work out the pressure and flow in every wet cell.
+///
+/// Water is routed from the pump stations along the shortest connected paths, so any loops in the network are
+/// treated as if the longer branch were closed. Each consumer draws its demand, the flow in a pipe is the total demand
+/// downstream of it and the head lost along it follows Hazen-Williams for the length and diameter of that piece.
+/// Pumps add a fixed head to whatever passes through.
+/// A station asked for more than its capacity can't supply any of the consumers it feeds.
+pub fn solve_hydraulics(
+    grid: &Grid,
+    equipment_ports: &HashMap<CellId, Vec<Direction>>,
+    equipment: &HashMap<CellId, EquipmentKind>,
+    fixtures: &HashMap<CellId, FixtureMeta>,
+    game_settings: &GameSettings,
+) -> HydraulicState {
+    let settings = &game_settings.hydraulics;
+    let mut order = Vec::new();
+    let mut parents = HashMap::new();
+    let mut stations = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for cell in grid.cells.values() {
+        if cell.feature == Some(Feature::PumpStation) {
+            parents.insert(cell.id, None);
+            stations.insert(cell.id, cell.id);
+            queue.push_back(cell.id);
+        }
+    }
+
+    while let Some(cell_idx) = queue.pop_front() {
+        order.push(cell_idx);
+        let cell = &grid.cells[&cell_idx];
+        if is_consumer(cell) {
+            continue;
+        }
+        for (_, neighbour_idx) in connections(grid, cell, equipment_ports) {
+            if let Entry::Vacant(entry) = parents.entry(neighbour_idx) {
+                entry.insert(Some(cell_idx));
+                stations.insert(neighbour_idx, stations[&cell_idx]);
+                queue.push_back(neighbour_idx);
+            }
+        }
+    }
+
+    // demands gather up the tree from the consumers towards the stations
+    let mut flows = HashMap::<CellId, f32>::new();
+    for cell_idx in order.iter().rev() {
+        let demand = fixtures.get(cell_idx).map(|fixture| fixture.demand()).unwrap_or_default();
+        let flow = *flows.entry(*cell_idx).or_default() + demand;
+        flows.insert(*cell_idx, flow);
+        if let Some(Some(parent_idx)) = parents.get(cell_idx) {
+            *flows.entry(*parent_idx).or_default() += flow;
+        }
+    }
+
+    // and head is lost along the way back down
+    let mut hydraulic_state = HydraulicState::default();
+    for cell_idx in order.iter() {
+        let flow = flows[cell_idx];
+        let pressure = match parents[cell_idx] {
+            None => settings.station_head,
+            Some(parent_idx) => {
+                let upstream = hydraulic_state.cells[&parent_idx].pressure;
+                let mut pressure = upstream;
+                if let Some(kind) = equipment.get(cell_idx) {
+                    if let Some(pipe) = &kind.asset_meta(&game_settings.assets).pipe {
+                        pressure -= head_loss(flow, pipe, settings.roughness);
+                    }
+                    if *kind == EquipmentKind::Pump {
+                        pressure += settings.pump_head;
+                    }
+                }
+                pressure
+            },
+        };
+        hydraulic_state.cells.insert(*cell_idx, CellHydraulics { pressure, flow });
+    }
+
+    // everything a station feeds has gathered in to its own flow
+    for (cell_idx, parent) in parents.iter() {
+        let capacity = fixtures.get(cell_idx).map(|fixture| fixture.capacity()).unwrap_or_default();
+        if parent.is_none() && flows[cell_idx] > capacity {
+            hydraulic_state.overloaded_stations.insert(*cell_idx);
+        }
+    }
+
+    for cell in grid.cells.values().filter(|cell| is_consumer(cell)) {
+        let overloaded = stations
+            .get(&cell.id)
+            .is_some_and(|station_idx| hydraulic_state.overloaded_stations.contains(station_idx));
+        let supplied = !overloaded && hydraulic_state.cells
+            .get(&cell.id)
+            .map(|cell_hydraulics| cell_hydraulics.pressure >= settings.min_pressure)
+            .unwrap_or(false);
+        if supplied {
+            hydraulic_state.supplied_consumers.insert(cell.id);
+        } else {
+            hydraulic_state.starved_consumers.insert(cell.id);
+        }
+    }
+
+    hydraulic_state
+}
+
+
+/// Hazen-Williams head loss in metres along a pipe carrying a flow in litres per second
+fn head_loss(flow: f32, pipe: &PipeSize, roughness: f32) -> f32 {
+    let flow_m3 = flow / 1000.0;
+    10.67 * pipe.length * flow_m3.powf(1.852)
+        / (roughness.powf(1.852) * pipe.diameter.powf(4.8704))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowy_core::level::Level;
+    use crate::flowy_core::flow::solve_flow;
+    use crate::flowy_core::test_support::{LEVEL, build_grid, fixtures, bridging_pipe, network};
+
+    #[test]
+    fn pipe_connects_and_supplies_the_home() {
+        let game_settings = GameSettings::built_in();
+        let level = Level::parse("test", LEVEL, 10).unwrap();
+        let grid = build_grid(&level);
+        let (ports, kinds) = network(&bridging_pipe());
+
+        let flow_network = solve_flow(&grid, &ports);
+        let hydraulic_state = solve_hydraulics(&grid, &ports, &kinds, &fixtures(&grid, &game_settings), &game_settings);
+
+        assert!(flow_network.all_consumers_connected());
+        assert!(flow_network.leaks.is_empty());
+        assert!(hydraulic_state.supplied_consumers.contains(&CellId::new(2, 0)));
+    }
+
+    #[test]
+    fn overloaded_station_starves_its_consumers() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.hydraulics.station_capacity = game_settings.hydraulics.home_demand / 2.0;
+        let level = Level::parse("test", LEVEL, 10).unwrap();
+        let grid = build_grid(&level);
+        let (ports, kinds) = network(&bridging_pipe());
+
+        let hydraulic_state = solve_hydraulics(&grid, &ports, &kinds, &fixtures(&grid, &game_settings), &game_settings);
+
+        assert!(hydraulic_state.overloaded_stations.contains(&CellId::new(0, 0)));
+        assert!(hydraulic_state.starved_consumers.contains(&CellId::new(2, 0)));
+    }
+}
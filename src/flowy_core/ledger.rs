@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use super::settings::GameSettings;
+use super::grid::CellId;
+use super::equipment::EquipmentKind;
+use super::level::Terrain;
+
+
+/// Whether money went out of the budget or came back in to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transaction {
+    Charge,
+    Refund,
+}
+
+
+/// The groups spending is totalled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CostCategory {
+    Pipes,
+    Fittings,
+    Pumps,
+}
+
+impl CostCategory {
+    pub const ALL: [CostCategory; 3] = [CostCategory::Pipes, CostCategory::Fittings, CostCategory::Pumps];
+
+    /// The category a piece of equipment is paid for under, the fixtures the level places are never paid for
+    pub fn for_kind(kind: EquipmentKind) -> Option<Self> {
+        match kind {
+            EquipmentKind::StraightPipe => Some(CostCategory::Pipes),
+            EquipmentKind::Bend | EquipmentKind::Tee | EquipmentKind::Quad => Some(CostCategory::Fittings),
+            EquipmentKind::Pump => Some(CostCategory::Pumps),
+            EquipmentKind::PumpStation | EquipmentKind::Home | EquipmentKind::Business => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CostCategory::Pipes => "Pipes",
+            CostCategory::Fittings => "Fittings",
+            CostCategory::Pumps => "Pumps",
+        }
+    }
+}
+
+
+/// A single charge or refund against the budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub transaction: Transaction,
+    pub kind: EquipmentKind,
+    pub cell_idx: CellId,
+    pub terrain: Terrain,
+    /// The cost of the equipment in the `[assets]` table before the terrain is taken into account
+    pub base_cost: i32,
+    /// The `cost_multiplier` of the terrain the equipment sits on
+    pub terrain_multiplier: f32,
+    /// What actually came out of or went back in to the budget, which is what the equipment was charged when it was
+    /// laid even if the settings have changed since
+    pub cost: i32,
+    /// How far in to the level it happened
+    pub at: Duration,
+}
+
+impl LedgerEntry {
+    pub fn new(
+        transaction: Transaction,
+        kind: EquipmentKind,
+        cell_idx: CellId,
+        terrain: Terrain,
+        cost: i32,
+        at: Duration,
+        game_settings: &GameSettings,
+    ) -> Self {
+        Self {
+            transaction,
+            kind,
+            cell_idx,
+            terrain,
+            base_cost: kind.asset_meta(&game_settings.assets).cost,
+            terrain_multiplier: terrain.settings(&game_settings.terrain).cost_multiplier,
+            cost,
+            at,
+        }
+    }
+
+    /// How much this entry adds to the money spent, refunds taking it away again
+    pub fn spent(&self) -> i32 {
+        match self.transaction {
+            Transaction::Charge => self.cost,
+            Transaction::Refund => -self.cost,
+        }
+    }
+}
+
+
+/// A line of the invoice, covering every piece of one kind of equipment left on one kind of terrain
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvoiceLine {
+    pub kind: EquipmentKind,
+    pub terrain: Terrain,
+    pub quantity: i32,
+    pub total: i32,
+}
+
+
+/// Every charge and refund made against the budget during a level, oldest first
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ledger {
+    entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    pub fn record(&mut self, entry: LedgerEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    /// The money spent on a category once refunds are taken off
+    pub fn total(&self, category: CostCategory) -> i32 {
+        self.entries
+            .iter()
+            .filter(|entry| CostCategory::for_kind(entry.kind) == Some(category))
+            .map(LedgerEntry::spent)
+            .sum()
+    }
+
+    /// The money spent on everything once refunds are taken off
+    pub fn spent(&self) -> i32 {
+        self.entries.iter().map(LedgerEntry::spent).sum()
+    }
+
+    /// What the money went on, with a line for each kind of equipment and terrain in the order they were first
+    /// bought. Anything that was bought and refunded again is left off.
+    pub fn invoice(&self) -> Vec<InvoiceLine> {
+        let mut lines: Vec<InvoiceLine> = Vec::new();
+        for entry in self.entries.iter() {
+            let quantity = match entry.transaction {
+                Transaction::Charge => 1,
+                Transaction::Refund => -1,
+            };
+            match lines.iter_mut().find(|line| line.kind == entry.kind && line.terrain == entry.terrain) {
+                Some(line) => {
+                    line.quantity += quantity;
+                    line.total += entry.spent();
+                },
+                None => lines.push(InvoiceLine {
+                    kind: entry.kind,
+                    terrain: entry.terrain,
+                    quantity,
+                    total: entry.spent(),
+                }),
+            }
+        }
+
+        lines.retain(|line| line.quantity != 0 || line.total != 0);
+        lines
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ledger_totals_and_invoice_leave_out_refunds() {
+        let game_settings = GameSettings::built_in();
+        let entry = |transaction, kind, column, terrain, cost| {
+            LedgerEntry::new(transaction, kind, CellId::new(column, 0), terrain, cost, Duration::ZERO, &game_settings)
+        };
+        let mut ledger = Ledger::default();
+
+        ledger.record(entry(Transaction::Charge, EquipmentKind::StraightPipe, 0, Terrain::Grass, 1));
+        ledger.record(entry(Transaction::Charge, EquipmentKind::StraightPipe, 1, Terrain::Grass, 1));
+        ledger.record(entry(Transaction::Charge, EquipmentKind::Bend, 2, Terrain::Grass, 2));
+        ledger.record(entry(Transaction::Refund, EquipmentKind::Bend, 2, Terrain::Grass, 2));
+
+        assert_eq!(ledger.total(CostCategory::Pipes), 2);
+        assert_eq!(ledger.total(CostCategory::Fittings), 0);
+        assert_eq!(ledger.spent(), 2);
+        assert_eq!(ledger.invoice(), vec![InvoiceLine {
+            kind: EquipmentKind::StraightPipe,
+            terrain: Terrain::Grass,
+            quantity: 2,
+            total: 2,
+        }]);
+    }
+}
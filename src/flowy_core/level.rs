@@ -0,0 +1,343 @@
+use std::fmt;
+use std::fs;
+
+use serde::Deserialize;
+
+use super::settings::{TerrainSettings, TerrainMeta, LevelMeta, asset_root};
+use super::budget::BudgetMode;
+
+
+/// The currently loaded level layout
+#[derive(Default, Debug)]
+pub struct Level {
+    /// The key of the level in the `[levels]` table, `None` if this is a blank board
+    pub id: Option<String>,
+    pub name: String,
+    pub columns: i32,
+    pub rows: i32,
+    pub start_budget: i32,
+    pub budget_mode: BudgetMode,
+    /// The tiles of the level in row major order, starting from the top left
+    pub tiles: Vec<Tile>,
+}
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub terrain: Terrain,
+    pub feature: Option<Feature>,
+}
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Terrain {
+    #[default]
+    Grass,
+    Road,
+    Rock,
+    River,
+    BuildingPlot,
+}
+
+
+/// Anything that is pre-placed on the board by the level and occupies its cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Blocked,
+    PumpStation,
+    Home,
+    Business,
+}
+
+
+/// The on disk layout of a level file
+#[derive(Deserialize, Debug)]
+struct LevelFile {
+    name: String,
+    /// One line per row of the grid and one character per cell, see the readme for the symbols
+    tiles: String,
+}
+
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    Empty,
+    RaggedRow { row: usize, expected: usize, found: usize },
+    UnknownTile { column: usize, row: usize, symbol: char },
+    /// The level's grid is too big to lay out in pixels at the cell size
+    TooLarge { columns: i32, rows: i32, cell_width: i32, cell_height: i32 },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "could not read level file: {}", err),
+            LevelError::Toml(err) => write!(f, "could not parse level file: {}", err),
+            LevelError::Empty => write!(f, "level has no tiles"),
+            LevelError::RaggedRow { row, expected, found } => write!(f, "row {} has {} tiles, expected {}", row, found, expected),
+            LevelError::UnknownTile { column, row, symbol } => write!(f, "unknown tile '{}' at column {}, row {}", symbol, column, row),
+            LevelError::TooLarge { columns, rows, cell_width, cell_height } => {
+                write!(f, "{}x{} tiles is too large for {}x{} cells", columns, rows, cell_width, cell_height)
+            },
+        }
+    }
+}
+
+
+impl Terrain {
+    pub fn settings<'a>(&self, terrain_settings: &'a TerrainSettings) -> &'a TerrainMeta {
+        match self {
+            Terrain::Grass => &terrain_settings.grass,
+            Terrain::Road => &terrain_settings.road,
+            Terrain::Rock => &terrain_settings.rock,
+            Terrain::River => &terrain_settings.river,
+            Terrain::BuildingPlot => &terrain_settings.building_plot,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Terrain::Grass => "Grass",
+            Terrain::Road => "Road",
+            Terrain::Rock => "Rock",
+            Terrain::River => "River",
+            Terrain::BuildingPlot => "Building Plot",
+        }
+    }
+}
+
+
+impl Tile {
+    fn from_symbol(symbol: char) -> Option<Self> {
+        let (terrain, feature) = match symbol {
+            '.' => (Terrain::Grass, None),
+            '=' => (Terrain::Road, None),
+            '^' => (Terrain::Rock, None),
+            '~' => (Terrain::River, None),
+            '_' => (Terrain::BuildingPlot, None),
+            '#' => (Terrain::Grass, Some(Feature::Blocked)),
+            'P' => (Terrain::BuildingPlot, Some(Feature::PumpStation)),
+            'H' => (Terrain::BuildingPlot, Some(Feature::Home)),
+            'B' => (Terrain::BuildingPlot, Some(Feature::Business)),
+            _ => return None,
+        };
+
+        Some(Self { terrain, feature })
+    }
+}
+
+
+impl Level {
+    /// An empty grass board, used when there are no levels configured or the level fails to load
+    pub fn blank(columns: i32, rows: i32, start_budget: i32) -> Self {
+        Self {
+            id: None,
+            name: String::from("Sandbox"),
+            columns,
+            rows,
+            start_budget,
+            budget_mode: BudgetMode::default(),
+            tiles: vec![Tile::default(); (columns * rows) as usize],
+        }
+    }
+
+    /// Read a level from its file, found from the asset root like the textures so it doesn't matter where the game is
+    /// started from
+    pub fn load(id: &str, level_meta: &LevelMeta) -> Result<Self, LevelError> {
+        let contents = fs::read_to_string(asset_root().join(&level_meta.path)).map_err(LevelError::Io)?;
+        Ok(Self {
+            budget_mode: level_meta.budget_mode,
+            ..Self::parse(id, &contents, level_meta.start_budget)?
+        })
+    }
+
+    /// Read a level from the contents of a level file
+    pub fn parse(id: &str, contents: &str, start_budget: i32) -> Result<Self, LevelError> {
+        let level_file: LevelFile = toml::from_str(contents).map_err(LevelError::Toml)?;
+        let (columns, rows, tiles) = parse_tiles(&level_file.tiles)?;
+
+        Ok(Self {
+            id: Some(id.to_string()),
+            name: level_file.name,
+            columns,
+            rows,
+            start_budget,
+            budget_mode: BudgetMode::default(),
+            tiles,
+        })
+    }
+
+    /// Check the level's grid can be laid out in pixels with cells of the given size
+    pub fn check_fits(&self, cell_width: i32, cell_height: i32) -> Result<(), LevelError> {
+        match (self.columns.checked_mul(cell_width), self.rows.checked_mul(cell_height)) {
+            (Some(_), Some(_)) => Ok(()),
+            _ => Err(LevelError::TooLarge { columns: self.columns, rows: self.rows, cell_width, cell_height }),
+        }
+    }
+
+    /// Get the tile at a column and row, with (0, 0) being the top left of the board
+    pub fn tile(&self, column: i32, row: i32) -> Option<&Tile> {
+        if column < 0 || row < 0 || column >= self.columns || row >= self.rows {
+            return None;
+        }
+        self.tiles.get((row * self.columns + column) as usize)
+    }
+}
+
+
+fn parse_tiles(layout: &str) -> Result<(i32, i32, Vec<Tile>), LevelError> {
+    let lines = layout
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>();
+
+    let columns = lines.first().ok_or(LevelError::Empty)?.chars().count();
+    let mut tiles = Vec::with_capacity(columns * lines.len());
+
+    for (row, line) in lines.iter().enumerate() {
+        let found = line.chars().count();
+        if found != columns {
+            return Err(LevelError::RaggedRow { row, expected: columns, found });
+        }
+        for (column, symbol) in line.chars().enumerate() {
+            tiles.push(Tile::from_symbol(symbol).ok_or(LevelError::UnknownTile { column, row, symbol })?);
+        }
+    }
+
+    Ok((columns as i32, lines.len() as i32, tiles))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, HashMap, HashSet};
+
+    use super::*;
+    use crate::flowy_core::settings::GameSettings;
+    use crate::flowy_core::grid::{Grid, CellId, Direction};
+    use crate::flowy_core::equipment::{EquipmentKind, Rotation, placement_cost};
+    use crate::flowy_core::flow::solve_flow;
+    use crate::flowy_core::hydraulics::solve_hydraulics;
+    use crate::flowy_core::test_support::{build_grid, fixtures, network};
+
+    /// Lay pipes from the pump stations to every consumer, joining the closest consumer to what is already laid
+    /// along the cheapest run of straight pipe each time and fitting each piece to the runs it joins
+    fn route_to_consumers(grid: &Grid, game_settings: &GameSettings) -> Vec<(CellId, EquipmentKind, Rotation)> {
+        let mut sources = grid.cells
+            .values()
+            .filter(|cell| cell.feature == Some(Feature::PumpStation))
+            .map(|cell| cell.id)
+            .collect::<HashSet<CellId>>();
+        let mut consumers = grid.cells
+            .values()
+            .filter(|cell| matches!(cell.feature, Some(Feature::Home) | Some(Feature::Business)))
+            .map(|cell| cell.id)
+            .collect::<HashSet<CellId>>();
+        let mut sides: HashMap<CellId, Vec<Direction>> = HashMap::new();
+
+        while !consumers.is_empty() {
+            // search out from everything already laid, cheapest ground first, until the nearest consumer is reached
+            let mut parents = HashMap::new();
+            let mut costs = HashMap::new();
+            let mut queue = BinaryHeap::new();
+            for cell_idx in &sources {
+                costs.insert(*cell_idx, 0);
+                parents.insert(*cell_idx, None);
+                queue.push(Reverse((0, cell_idx.row, cell_idx.column)));
+            }
+            let mut reached = None;
+            while let Some(Reverse((cost, row, column))) = queue.pop() {
+                let cell_idx = CellId::new(column, row);
+                if cost > costs[&cell_idx] {
+                    continue;
+                }
+                if consumers.contains(&cell_idx) {
+                    reached = Some(cell_idx);
+                    break;
+                }
+                let cell = &grid.cells[&cell_idx];
+                for direction in Direction::ALL {
+                    let Some(neighbour) = cell.neighbours.get(direction).and_then(|idx| grid.cells.get(&idx)) else {
+                        continue;
+                    };
+                    let step = if consumers.contains(&neighbour.id) {
+                        0
+                    } else if neighbour.feature.is_none() && neighbour.terrain.settings(&game_settings.terrain).placeable {
+                        placement_cost(EquipmentKind::StraightPipe, neighbour.terrain, game_settings)
+                    } else {
+                        continue;
+                    };
+                    if costs.get(&neighbour.id).is_none_or(|known| cost + step < *known) {
+                        costs.insert(neighbour.id, cost + step);
+                        parents.insert(neighbour.id, Some((cell_idx, direction)));
+                        queue.push(Reverse((cost + step, neighbour.id.row, neighbour.id.column)));
+                    }
+                }
+            }
+
+            let consumer = reached.expect("every consumer can be reached");
+            consumers.remove(&consumer);
+            let mut cell_idx = consumer;
+            while let Some((parent_idx, direction)) = parents[&cell_idx] {
+                sides.entry(parent_idx).or_default().push(direction);
+                sides.entry(cell_idx).or_default().push(direction.opposite());
+                if grid.cells[&parent_idx].feature.is_none() {
+                    sources.insert(parent_idx);
+                }
+                cell_idx = parent_idx;
+            }
+        }
+
+        sides
+            .into_iter()
+            .filter(|(cell_idx, _)| grid.cells[cell_idx].feature.is_none())
+            .map(|(cell_idx, sides)| {
+                let (kind, rotation) = EquipmentKind::fitting_for(&sides);
+                (cell_idx, kind, rotation)
+            })
+            .collect()
+    }
+
+    /// Route a shipped level and check the network supplies every consumer within the level's budget
+    fn assert_winnable(level_id: &str) {
+        let game_settings = GameSettings::built_in();
+        let level = Level::load(level_id, &game_settings.levels[level_id]).unwrap();
+        let grid = build_grid(&level);
+        let pieces = route_to_consumers(&grid, &game_settings);
+        let (ports, kinds) = network(&pieces);
+
+        let flow_network = solve_flow(&grid, &ports);
+        let hydraulic_state = solve_hydraulics(&grid, &ports, &kinds, &fixtures(&grid, &game_settings), &game_settings);
+        let cost = pieces
+            .iter()
+            .map(|(cell_idx, kind, _)| placement_cost(*kind, grid.cells[cell_idx].terrain, &game_settings))
+            .sum::<i32>();
+
+        assert!(flow_network.all_consumers_connected(), "{level_id}: {flow_network:?}");
+        assert!(flow_network.leaks.is_empty(), "{level_id}: {:?}", flow_network.leaks);
+        assert!(hydraulic_state.all_consumers_supplied(), "{level_id}: {hydraulic_state:?}");
+        assert!(cost <= level.start_budget, "{level_id} costs {cost} of {}", level.start_budget);
+    }
+
+    #[test]
+    fn level_too_large_for_the_cell_size_is_reported() {
+        // only the size matters, so the tiles of a small board are left as they are
+        let level = Level { columns: 40_000_000, ..Level::blank(1, 1, 10) };
+
+        assert!(level.check_fits(64, 64).is_err());
+        assert!(level.check_fits(1, 64).is_ok());
+    }
+
+    #[test]
+    fn every_built_in_level_can_be_won() {
+        let game_settings = GameSettings::built_in();
+        assert!(!game_settings.levels.is_empty());
+        for level_id in game_settings.levels.keys() {
+            assert_winnable(level_id);
+        }
+    }
+}
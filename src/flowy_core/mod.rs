@@ -0,0 +1,17 @@
+//! The rules of the game with no Bevy in them: the settings, the grid, the equipment, the budget and its ledger, the
+//! timer and the flow and hydraulic solvers. The Bevy plugins in [`crate::game`] are a thin layer over these that
+//! draws the board and turns input into calls here, so a level can be checked without a window.
+
+pub mod settings;
+pub mod level;
+pub mod grid;
+pub mod equipment;
+pub mod budget;
+pub mod ledger;
+pub mod timer;
+pub mod flow;
+pub mod hydraulics;
+
+
+#[cfg(test)]
+mod test_support;
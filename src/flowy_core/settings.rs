@@ -0,0 +1,709 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::SETTINGS_STR;
+use super::budget::BudgetMode;
+
+
+/// The folder inside the platform config dir that the user settings are kept in
+const CONFIG_DIR_NAME: &str = "flowy";
+const USER_SETTINGS_FILE: &str = "settings.toml";
+/// Environment variables starting with this override a single setting, e.g. `FLOWY__GRID__CELL_WIDTH=32`
+const ENV_PREFIX: &str = "FLOWY__";
+/// Command line flag to override a single setting, e.g. `--set grid.cell_width=32`
+const CLI_FLAG: &str = "--set";
+
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GameSettings {
+    pub window: WindowGameSettings,
+    pub grid: GridGameSettings,
+    pub gameplay: GameplaySettings,
+    pub hydraulics: HydraulicSettings,
+    pub terrain: TerrainSettings,
+    pub assets: AssetSettings,
+    #[serde(default)]
+    pub levels: BTreeMap<String, LevelMeta>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        let game_settings = GameSettings::load().unwrap_or_else(|err| {
+            println!("Failed to load settings, using the built in ones: {}", err);
+            GameSettings::built_in()
+        });
+        println!("{:#?}", game_settings);
+        game_settings
+    }
+}
+
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(std::io::Error),
+    /// The file isn't valid toml
+    Parse(toml::de::Error),
+    /// The settings don't fit the expected layout, the message names the key that is wrong
+    Invalid(toml::de::Error),
+    Serialize(toml::ser::Error),
+    /// The settings are well formed but don't make sense together
+    Validation(ValidationError),
+    /// An environment or command line override that isn't written as `key=value`
+    BadOverride(String),
+    NoConfigDir,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "could not access settings file: {}", err),
+            SettingsError::Parse(err) => write!(f, "could not parse settings: {}", err),
+            SettingsError::Invalid(err) => write!(f, "invalid settings: {}", err),
+            SettingsError::Serialize(err) => write!(f, "could not write settings: {}", err),
+            SettingsError::Validation(err) => write!(f, "invalid settings: {}", err),
+            SettingsError::BadOverride(raw) => write!(f, "override '{}' should look like key.path=value", raw),
+            SettingsError::NoConfigDir => write!(f, "no config directory on this platform"),
+        }
+    }
+}
+
+
+impl GameSettings {
+    /// Load the settings in layers, each overriding the last: the defaults built in to the game, the user settings file
+    /// in the platform config dir, `FLOWY__` environment variables and finally `--set key=value` command line flags.
+    /// A layer that would leave the settings invalid is reported and skipped, so a bad file never stops the game.
+    /// Only built in settings that are themselves invalid are an error.
+    pub fn load() -> Result<Self, SettingsError> {
+        let (layered, problems) = layered_settings()?;
+        for problem in problems {
+            println!("{}", problem);
+        }
+        Self::from_value(&layered)
+    }
+
+    /// The settings built in to the game, without any user, environment or command line layers
+    pub fn built_in() -> Self {
+        // the built in settings are part of the build so they are always valid, see the tests
+        toml::from_str(SETTINGS_STR).unwrap()
+    }
+
+    fn from_value(value: &Value) -> Result<Self, SettingsError> {
+        // going back through text means errors point at the offending line and key
+        let contents = toml::to_string(value).map_err(SettingsError::Serialize)?;
+        let game_settings: GameSettings = toml::from_str(&contents).map_err(SettingsError::Invalid)?;
+        game_settings.validate().map_err(SettingsError::Validation)?;
+        Ok(game_settings)
+    }
+
+    fn to_value(&self) -> Result<Value, SettingsError> {
+        Value::try_from(self).map_err(SettingsError::Serialize)
+    }
+
+    /// Write the settings changed while the game has been running in to the user settings file, keeping anything already in it
+    pub fn save_user_settings(&self) -> Result<(), SettingsError> {
+        let path = user_settings_path().ok_or(SettingsError::NoConfigDir)?;
+
+        // compare like with like, as floats pick up noise going through the settings types
+        let (layered, _) = layered_settings()?;
+        let startup = Self::from_value(&layered)?.to_value()?;
+        let Some(changes) = changed_values(&startup, &self.to_value()?) else {
+            return Ok(());
+        };
+
+        let mut user_settings = match read_user_settings(&path) {
+            Ok(Some(user_settings)) => user_settings,
+            _ => Value::Table(Table::new()),
+        };
+        merge_values(&mut user_settings, changes);
+
+        let contents = toml::to_string(&user_settings).map_err(SettingsError::Serialize)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(SettingsError::Io)?;
+        }
+        fs::write(&path, contents).map_err(SettingsError::Io)?;
+        println!("Settings saved to {}", path.display());
+        Ok(())
+    }
+}
+
+
+/// A setting that would break the game, naming the key it was found at
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    /// A size that has to be greater than zero, like a cell or window dimension
+    NotPositive { key: String, value: f32 },
+    /// A cell is bigger than the window, so a blank board wouldn't have any cells in it
+    CellLargerThanWindow { key: String, cell_size: i32, size: i32 },
+    /// An asset points at a texture that isn't under the asset root
+    MissingAsset { name: String, path: String },
+    /// Two assets share a name, so one couldn't be told apart from the other
+    DuplicateAssetName { name: String },
+    /// A cost, cost multiplier or budget below zero
+    Negative { key: String, value: f32 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::NotPositive { key, value } => write!(f, "{} must be greater than zero, found {}", key, value),
+            ValidationError::CellLargerThanWindow { key, cell_size, size } => write!(f, "{} of {} is larger than the window's {}", key, cell_size, size),
+            ValidationError::MissingAsset { name, path } => write!(f, "asset '{}' points at '{}' which does not exist", name, path),
+            ValidationError::DuplicateAssetName { name } => write!(f, "more than one asset is named '{}'", name),
+            ValidationError::Negative { key, value } => write!(f, "{} must not be negative, found {}", key, value),
+        }
+    }
+}
+
+
+impl GameSettings {
+    /// Check the settings make sense before anything is built from them, returning the first problem found
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let resolution = &self.window.resolution;
+        let positive = [
+            ("window.resolution.width", resolution.width as f32),
+            ("window.resolution.height", resolution.height as f32),
+            ("grid.cell_width", self.grid.cell_width as f32),
+            ("grid.cell_height", self.grid.cell_height as f32),
+            ("hydraulics.roughness", self.hydraulics.roughness),
+            ("hydraulics.station_capacity", self.hydraulics.station_capacity),
+        ];
+        for (key, value) in positive {
+            if value <= 0.0 {
+                return Err(ValidationError::NotPositive { key: key.to_string(), value });
+            }
+        }
+        if let Some(chunk_size) = self.grid.chunk_size {
+            if chunk_size <= 0 {
+                return Err(ValidationError::NotPositive { key: String::from("grid.chunk_size"), value: chunk_size as f32 });
+            }
+        }
+
+        // the board is letterboxed in to the window, so the window no longer has to be a whole number of cells and
+        // leaves no gaps if it isn't. A blank board is sized from the window though, so it has to fit at least a cell
+        let fits = [
+            ("grid.cell_width", self.grid.cell_width, resolution.width),
+            ("grid.cell_height", self.grid.cell_height, resolution.height),
+        ];
+        for (key, cell_size, size) in fits {
+            if cell_size > size {
+                return Err(ValidationError::CellLargerThanWindow { key: key.to_string(), cell_size, size });
+            }
+        }
+
+        let mut names = Vec::new();
+        for (asset, asset_meta) in self.assets.iter() {
+            if names.contains(&&asset_meta.name) {
+                return Err(ValidationError::DuplicateAssetName { name: asset_meta.name.clone() });
+            }
+            names.push(&asset_meta.name);
+
+            if asset_meta.cost < 0 {
+                return Err(ValidationError::Negative { key: format!("assets.{}.cost", asset), value: asset_meta.cost as f32 });
+            }
+            if let Some(pipe) = &asset_meta.pipe {
+                for (field, value) in [("length", pipe.length), ("diameter", pipe.diameter)] {
+                    if value <= 0.0 {
+                        return Err(ValidationError::NotPositive { key: format!("assets.{}.pipe.{}", asset, field), value });
+                    }
+                }
+            }
+            asset_meta.check_path()?;
+        }
+
+        let mut non_negative = vec![
+            (String::from("gameplay.budget"), self.gameplay.budget as f32),
+            (String::from("gameplay.overdraft_penalty"), self.gameplay.overdraft_penalty as f32),
+            (String::from("hydraulics.home_demand"), self.hydraulics.home_demand),
+            (String::from("hydraulics.business_demand"), self.hydraulics.business_demand),
+        ];
+        for (terrain, terrain_meta) in self.terrain.iter() {
+            non_negative.push((format!("terrain.{}.cost_multiplier", terrain), terrain_meta.cost_multiplier));
+        }
+        for (level, level_meta) in self.levels.iter() {
+            non_negative.push((format!("levels.{}.start_budget", level), level_meta.start_budget as f32));
+        }
+        for (key, value) in non_negative {
+            if value < 0.0 {
+                return Err(ValidationError::Negative { key, value });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Where the user settings file lives, if the platform has a config dir
+pub fn user_settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(USER_SETTINGS_FILE))
+}
+
+
+/// The folder asset paths are relative to, found the same way Bevy's asset server finds it: `BEVY_ASSET_ROOT`, then
+/// the crate folder when run through cargo, otherwise the folder the executable is in
+pub fn asset_root() -> PathBuf {
+    if let Ok(root) = std::env::var("BEVY_ASSET_ROOT") {
+        return PathBuf::from(root);
+    }
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        return PathBuf::from(manifest_dir);
+    }
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+}
+
+
+/// The settings built in to the game as the bottom layer, checked like any other layer
+fn base_settings(contents: &str) -> Result<Value, SettingsError> {
+    let base = Value::Table(contents.parse::<Table>().map_err(SettingsError::Parse)?);
+    GameSettings::from_value(&base)?;
+    Ok(base)
+}
+
+
+/// Stack every settings layer on to the built in defaults, returning what was skipped along the way
+fn layered_settings() -> Result<(Value, Vec<String>), SettingsError> {
+    let mut layered = base_settings(SETTINGS_STR)?;
+    let mut problems = Vec::new();
+
+    let mut layers = Vec::new();
+    if let Some(path) = user_settings_path() {
+        layers.push((path.display().to_string(), read_user_settings(&path)));
+    }
+    for (name, raw) in env_overrides() {
+        layers.push((format!("environment variable {}", name), parse_override(&raw).map(Some)));
+    }
+    for raw in cli_overrides() {
+        layers.push((format!("command line override {}", raw), parse_override(&raw).map(Some)));
+    }
+
+    for (source, layer) in layers {
+        match layer {
+            Ok(Some(layer)) => {
+                let mut candidate = layered.clone();
+                merge_values(&mut candidate, layer);
+                match GameSettings::from_value(&candidate) {
+                    Ok(_) => layered = candidate,
+                    Err(err) => problems.push(format!("Ignoring settings from {}: {}", source, err)),
+                }
+            },
+            Ok(None) => {},
+            Err(err) => problems.push(format!("Ignoring settings from {}: {}", source, err)),
+        }
+    }
+
+    Ok((layered, problems))
+}
+
+
+/// The user settings file, or nothing if it hasn't been written yet
+fn read_user_settings(path: &PathBuf) -> Result<Option<Value>, SettingsError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path).map_err(SettingsError::Io)?;
+    contents
+        .parse::<Table>()
+        .map(|table| Some(Value::Table(table)))
+        .map_err(SettingsError::Parse)
+}
+
+
+/// Environment overrides as `key.path=value`, with `FLOWY__GRID__CELL_WIDTH=32` becoming `grid.cell_width=32`
+fn env_overrides() -> Vec<(String, String)> {
+    let mut overrides = std::env::vars()
+        .filter_map(|(name, value)| {
+            let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase().replace("__", ".");
+            Some((name, format!("{}={}", key, value)))
+        })
+        .collect::<Vec<(String, String)>>();
+    // apply in a stable order whatever order the platform lists them in
+    overrides.sort();
+    overrides
+}
+
+
+/// Command line overrides from every `--set key.path=value` pair
+fn cli_overrides() -> Vec<String> {
+    let args = std::env::args().collect::<Vec<String>>();
+    args.windows(2)
+        .filter(|pair| pair[0] == CLI_FLAG)
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+
+/// Turn `key.path=value` in to a table holding just that setting. The value is read as toml so numbers and
+/// booleans keep their type, anything that isn't valid toml is taken as a plain string.
+fn parse_override(raw: &str) -> Result<Value, SettingsError> {
+    let Some((key_path, raw_value)) = raw.split_once('=') else {
+        return Err(SettingsError::BadOverride(raw.to_string()));
+    };
+    let keys = key_path.trim().split('.').collect::<Vec<&str>>();
+    if keys.iter().any(|key| key.is_empty()) {
+        return Err(SettingsError::BadOverride(raw.to_string()));
+    }
+
+    let raw_value = raw_value.trim();
+    let value = format!("value = {}", raw_value)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw_value.to_string()));
+
+    Ok(keys.iter().rev().fold(value, |value, key| {
+        let mut table = Table::new();
+        table.insert(key.to_string(), value);
+        Value::Table(table)
+    }))
+}
+
+
+/// Lay one set of settings over another, replacing any value that is in both
+fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    },
+                }
+            }
+        },
+        (base, overlay) => *base = overlay,
+    }
+}
+
+
+/// Just the settings in `current` that are different to `base`
+fn changed_values(base: &Value, current: &Value) -> Option<Value> {
+    match (base, current) {
+        (Value::Table(base), Value::Table(current)) => {
+            let changes = current
+                .iter()
+                .filter_map(|(key, value)| match base.get(key) {
+                    Some(base_value) => changed_values(base_value, value).map(|change| (key.clone(), change)),
+                    None => Some((key.clone(), value.clone())),
+                })
+                .collect::<Table>();
+            (!changes.is_empty()).then_some(Value::Table(changes))
+        },
+        (base, current) => (base != current).then(|| current.clone()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WindowGameSettings {
+    pub resolution: ResolutionGameSettings,
+    pub resizable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ResolutionGameSettings {
+    pub width: i32,
+    pub height: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GridGameSettings {
+    pub cell_width: i32,
+    pub cell_height: i32,
+    /// Store the grid in square chunks of this many cells a side, for maps far bigger than the window
+    #[serde(default)]
+    pub chunk_size: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GameplaySettings {
+    pub budget: i32,
+    /// Points taken off the score for every unit a level in overdraft mode ends below zero
+    pub overdraft_penalty: i32,
+}
+
+/// Constants for the steady state hydraulic model. Heads and pressures are in metres of water and demands are in litres per second.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HydraulicSettings {
+    /// Hazen-Williams roughness coefficient of the pipes, the length and diameter of each piece is set in `[assets]`
+    pub roughness: f32,
+    pub station_head: f32,
+    pub pump_head: f32,
+    /// The most water a single pump station can supply
+    pub station_capacity: f32,
+    pub home_demand: f32,
+    pub business_demand: f32,
+    /// The pressure a consumer needs to count as supplied
+    pub min_pressure: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TerrainSettings {
+    pub grass: TerrainMeta,
+    pub road: TerrainMeta,
+    pub rock: TerrainMeta,
+    pub river: TerrainMeta,
+    pub building_plot: TerrainMeta,
+}
+
+impl TerrainSettings {
+    /// Every terrain along with its key in the `[terrain]` table
+    pub fn iter(&self) -> [(&'static str, &TerrainMeta); 5] {
+        [
+            ("grass", &self.grass),
+            ("road", &self.road),
+            ("rock", &self.rock),
+            ("river", &self.river),
+            ("building_plot", &self.building_plot),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TerrainMeta {
+    /// Scales the cost of any equipment placed on this terrain
+    pub cost_multiplier: f32,
+    /// Whether equipment can be placed on this terrain at all
+    pub placeable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AssetSettings {
+    pub straight_pipe: AssetMeta,
+    pub bend: AssetMeta,
+    pub tee: AssetMeta,
+    pub quad: AssetMeta,
+    pub pump: AssetMeta,
+    pub pump_station: AssetMeta,
+    pub home: AssetMeta,
+    pub business: AssetMeta,
+}
+
+impl AssetSettings {
+    /// Every asset along with its key in the `[assets]` table
+    pub fn iter(&self) -> [(&'static str, &AssetMeta); 8] {
+        [
+            ("straight_pipe", &self.straight_pipe),
+            ("bend", &self.bend),
+            ("tee", &self.tee),
+            ("quad", &self.quad),
+            ("pump", &self.pump),
+            ("pump_station", &self.pump_station),
+            ("home", &self.home),
+            ("business", &self.business),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AssetMeta {
+    pub name: String,
+    /// The texture for the asset, without one it is drawn in its colour
+    #[serde(default)]
+    pub path: Option<String>,
+    pub cost: i32,
+    /// The size of the pipe running through the piece, pieces without one lose no head
+    #[serde(default)]
+    pub pipe: Option<PipeSize>,
+}
+
+impl AssetMeta {
+    /// Check the texture, if the asset has one, is there under the asset root
+    pub fn check_path(&self) -> Result<(), ValidationError> {
+        match &self.path {
+            Some(path) if !asset_root().join(path).exists() => {
+                Err(ValidationError::MissingAsset { name: self.name.clone(), path: path.clone() })
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The pipe inside a piece of equipment, in metres
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PipeSize {
+    /// Length of the run through the cell
+    pub length: f32,
+    /// Internal diameter
+    pub diameter: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LevelMeta {
+    pub path: String,
+    pub start_budget: i32,
+    /// Whether equipment the player can't afford is blocked or allowed to overdraw the budget
+    #[serde(default)]
+    pub budget_mode: BudgetMode,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_settings_are_valid() {
+        assert_eq!(GameSettings::built_in().validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_cell_width_is_not_positive() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.grid.cell_width = 0;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::NotPositive { key: String::from("grid.cell_width"), value: 0.0 })
+        );
+    }
+
+    #[test]
+    fn negative_window_height_is_not_positive() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.window.resolution.height = -640;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::NotPositive { key: String::from("window.resolution.height"), value: -640.0 })
+        );
+    }
+
+    #[test]
+    fn zero_pipe_diameter_is_not_positive() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.assets.tee.pipe = Some(PipeSize { length: 10.0, diameter: 0.0 });
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::NotPositive { key: String::from("assets.tee.pipe.diameter"), value: 0.0 })
+        );
+    }
+
+    #[test]
+    fn zero_chunk_size_is_not_positive() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.grid.chunk_size = Some(0);
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::NotPositive { key: String::from("grid.chunk_size"), value: 0.0 })
+        );
+    }
+
+    #[test]
+    fn window_must_fit_a_cell() {
+        let mut game_settings = GameSettings::built_in();
+        // a window that isn't a whole number of cells is letterboxed
+        game_settings.window.resolution.width = 650;
+        assert_eq!(game_settings.validate(), Ok(()));
+
+        game_settings.window.resolution.height = 50;
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::CellLargerThanWindow { key: String::from("grid.cell_height"), cell_size: 64, size: 50 })
+        );
+    }
+
+    #[test]
+    fn missing_asset_path_is_reported() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.assets.tee.path = Some(String::from("assets/does_not_exist.png"));
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::MissingAsset { name: String::from("tee"), path: String::from("assets/does_not_exist.png") })
+        );
+    }
+
+    #[test]
+    fn asset_names_must_be_unique() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.assets.bend.name = String::from("straight_pipe");
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::DuplicateAssetName { name: String::from("straight_pipe") })
+        );
+    }
+
+    #[test]
+    fn negative_asset_cost_is_reported() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.assets.pump.cost = -10;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::Negative { key: String::from("assets.pump.cost"), value: -10.0 })
+        );
+    }
+
+    #[test]
+    fn negative_terrain_multiplier_is_reported() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.terrain.rock.cost_multiplier = -1.0;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::Negative { key: String::from("terrain.rock.cost_multiplier"), value: -1.0 })
+        );
+    }
+
+    #[test]
+    fn negative_budget_is_reported() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.gameplay.budget = -1;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::Negative { key: String::from("gameplay.budget"), value: -1.0 })
+        );
+    }
+
+    #[test]
+    fn negative_level_budget_is_reported() {
+        let mut game_settings = GameSettings::built_in();
+        game_settings.levels.get_mut("level_1").unwrap().start_budget = -5;
+
+        assert_eq!(
+            game_settings.validate(),
+            Err(ValidationError::Negative { key: String::from("levels.level_1.start_budget"), value: -5.0 })
+        );
+    }
+
+    #[test]
+    fn invalid_base_layer_is_an_error() {
+        let contents = SETTINGS_STR.replacen("cell_width = 64", "cell_width = 0", 1);
+
+        match base_settings(&contents) {
+            Err(SettingsError::Validation(err)) => assert!(err.to_string().contains("grid.cell_width")),
+            other => panic!("expected a validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn asset_paths_are_found_from_the_asset_root() {
+        // through cargo the root is the crate folder, wherever the game is started from
+        let root = asset_root();
+        assert!(root.is_absolute());
+        assert!(root.join("game_settings.toml").exists());
+    }
+
+    #[test]
+    fn invalid_layer_falls_back_with_the_key_named() {
+        let mut layered = Value::Table(SETTINGS_STR.parse::<Table>().unwrap());
+        merge_values(&mut layered, parse_override("grid.cell_width=0").unwrap());
+
+        match GameSettings::from_value(&layered) {
+            Err(SettingsError::Validation(err)) => assert!(err.to_string().contains("grid.cell_width")),
+            other => panic!("expected a validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
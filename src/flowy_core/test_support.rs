@@ -0,0 +1,45 @@
+//! Levels and networks shared by the tests of the core modules
+
+use std::collections::HashMap;
+
+use super::settings::GameSettings;
+use super::level::Level;
+use super::grid::{Grid, GridSettings, CellId, Direction};
+use super::equipment::{EquipmentKind, Rotation, FixtureMeta};
+use super::flow::equipment_ports;
+
+
+/// A station and a home with a single gap between them
+pub const LEVEL: &str = r#"
+    name = "Test"
+    tiles = """
+    P.H
+    """
+"#;
+
+pub fn build_grid(level: &Level) -> Grid {
+    Grid::build(&GridSettings::for_level(64, 64, level), level, None)
+}
+
+/// The fixture metadata for every pump station, home and business on the grid
+pub fn fixtures(grid: &Grid, game_settings: &GameSettings) -> HashMap<CellId, FixtureMeta> {
+    grid.cells
+        .values()
+        .filter_map(|cell| {
+            let (_, meta) = FixtureMeta::for_feature(cell.feature?, &game_settings.hydraulics)?;
+            Some((cell.id, meta))
+        })
+        .collect()
+}
+
+/// A straight pipe across the gap, turned to run east to west
+pub fn bridging_pipe() -> Vec<(CellId, EquipmentKind, Rotation)> {
+    vec![(CellId::new(1, 0), EquipmentKind::StraightPipe, Rotation::Quarter)]
+}
+
+/// The open ports and the kind of every piece laid
+pub fn network(pieces: &[(CellId, EquipmentKind, Rotation)]) -> (HashMap<CellId, Vec<Direction>>, HashMap<CellId, EquipmentKind>) {
+    let ports = equipment_ports(pieces.iter().copied());
+    let kinds = pieces.iter().map(|(cell_idx, kind, _)| (*cell_idx, *kind)).collect();
+    (ports, kinds)
+}
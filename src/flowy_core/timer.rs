@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+
+/// Counts down the time left to finish a level
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelTimer {
+    duration: Duration,
+    elapsed: Duration,
+}
+
+impl LevelTimer {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self {
+            duration: Duration::from_secs_f32(seconds),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Move the timer on, returning true on the tick it runs out
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        let was_finished = self.finished();
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        !was_finished && self.finished()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed.min(self.duration);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration - self.elapsed
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        self.remaining().as_secs_f32()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_reports_running_out_once() {
+        let mut timer = LevelTimer::from_seconds(1.0);
+
+        assert!(!timer.tick(Duration::from_millis(600)));
+        assert!(timer.tick(Duration::from_millis(600)));
+        assert!(!timer.tick(Duration::from_millis(600)));
+        assert_eq!(timer.remaining_secs(), 0.0);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::{
+    prelude::*,
+    input::mouse::{MouseMotion, MouseWheel},
+    render::camera::ScalingMode,
+    window::PrimaryWindow,
+};
+
+use crate::game::grid::GridSettings;
+use crate::AppState;
+
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Game), fit_camera_to_grid)
+            .add_systems(Update, (pan_camera, zoom_camera)
+                .run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), reset_camera);
+    }
+}
+
+
+/// How far the camera moves with the keyboard in screen pixels per second
+const PAN_SPEED: f32 = 400.0;
+/// How much each notch of the mouse wheel zooms by
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+
+
+/// Whether the zoom modifier is held, without it the mouse wheel selects equipment instead
+pub fn zoom_modifier_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+
+/// Show the whole board whatever the size of the window. The board keeps its aspect ratio, leaving bars either side
+/// when the window is a different shape, and is refitted by the projection every time the window is resized.
+fn fit_camera_to_grid(
+    grid_settings: Res<GridSettings>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    if let Ok((mut transform, mut projection)) = q_camera.get_single_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scale = 1.0;
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: grid_settings.grid_width as f32,
+            min_height: grid_settings.grid_height as f32,
+        };
+    }
+}
+
+
+/// Move the camera with WASD or by dragging with the middle mouse button
+fn pan_camera(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let Ok((mut transform, projection)) = q_camera.get_single_mut() else {
+        return;
+    };
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    // how much world a single pixel of the window covers at the current zoom and window size
+    let world_per_pixel = projection.area.width() / window.width().max(1.0);
+
+    // held modifiers are for shortcuts like undo, not for moving
+    let mut direction = Vec2::ZERO;
+    if !zoom_modifier_pressed(&keyboard_input) {
+        if keyboard_input.pressed(KeyCode::W) {
+            direction.y += 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::S) {
+            direction.y -= 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::A) {
+            direction.x -= 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::D) {
+            direction.x += 1.0;
+        }
+    }
+    // pan the same distance on screen however far we are zoomed in
+    let mut offset = direction.normalize_or_zero() * PAN_SPEED * world_per_pixel * time.delta_seconds();
+
+    // read every motion event so they don't build up while the button is released
+    let drag = mouse_motion.read().map(|motion| motion.delta).sum::<Vec2>();
+    if mouse_input.pressed(MouseButton::Middle) {
+        // screen y points down so the world follows the mouse by moving the other way
+        offset += Vec2::new(-drag.x, drag.y) * world_per_pixel;
+    }
+
+    transform.translation += offset.extend(0.0);
+}
+
+
+/// Zoom the camera with the mouse wheel while holding Ctrl
+fn zoom_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut scroll_events: EventReader<MouseWheel>,
+    mut q_camera: Query<&mut OrthographicProjection, With<Camera2d>>,
+) {
+    let steps = scroll_events
+        .read()
+        .map(|event| event.y.signum())
+        .sum::<f32>();
+    if steps == 0.0 || !zoom_modifier_pressed(&keyboard_input) {
+        return;
+    }
+
+    if let Ok(mut projection) = q_camera.get_single_mut() {
+        // scrolling up zooms in, which makes the world smaller per pixel
+        projection.scale = (projection.scale * ZOOM_STEP.powf(-steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+
+/// Put the camera back to one world unit per pixel for the menus
+fn reset_camera(
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    if let Ok((mut transform, mut projection)) = q_camera.get_single_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scale = 1.0;
+        projection.scaling_mode = ScalingMode::WindowSize(1.0);
+    }
+}
@@ -1,6 +1,16 @@
-use bevy::prelude::*;
-
-use crate::utils::colours::{GamePallete, get_colour};
+use bevy::{
+    prelude::*,
+    input::mouse::MouseWheel,
+};
+
+use crate::utils::{
+    colours::{GamePallete, get_colour},
+    game_settings::GameSettings,
+    ui::{HudCorner, hud_style},
+};
+use crate::game::grid::{CellId, CursorGridIdx, Grid};
+use crate::game::equipment::{kind::EquipmentKind, placement_cost};
+use crate::game::camera::zoom_modifier_pressed;
 use crate::AppState;
 use crate::game::SimulationState;
 
@@ -10,8 +20,9 @@ pub struct CursorPlugin;
 impl Plugin for CursorPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<SelectedEquipment>()
             .add_systems(OnEnter(AppState::Game), (spawn_cursor, define_cursor_mode).chain())
-            .add_systems(Update, (handle_mouse_click, render_cursor_mode_text)
+            .add_systems(Update, (handle_mouse_click, cycle_selected_equipment, render_cursor_mode_text)
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)))
             .add_systems(OnExit(AppState::Game), (cleanup_cursor, cleanup_cursor_text));
@@ -19,7 +30,10 @@ impl Plugin for CursorPlugin {
 }
 
 #[derive(Component, Default, Debug)]
-pub struct Cursor;
+pub struct Cursor {
+    /// The cells dragged across since placement mode started, in the order they were visited
+    pub path: Vec<CellId>
+}
 
 #[derive(Component, Default, Debug)]
 pub struct PlacingComponents;
@@ -31,11 +45,25 @@ pub struct DeletingComponents;
 #[derive(Component, Default)]
 pub struct CursorModeText; 
 
+/// The kind of equipment that is placed in placement mode
+#[derive(Resource, Debug)]
+pub struct SelectedEquipment {
+    pub kind: EquipmentKind
+}
+
+impl Default for SelectedEquipment {
+    fn default() -> Self {
+        Self {
+            kind: EquipmentKind::StraightPipe
+        }
+    }
+}
+
 
 fn spawn_cursor(
     mut commands: Commands
 ) {
-    commands.spawn(Cursor);
+    commands.spawn(Cursor::default());
 }
 
 /// Start or end placement/deletion mode when the mouse is clicked.
@@ -72,6 +100,33 @@ pub fn handle_mouse_click(
     }
 }
 
+/// Scroll through the placeable equipment with the mouse wheel, wrapping around at either end.
+/// Scrolling with the zoom modifier held zooms the camera instead.
+pub fn cycle_selected_equipment(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut scroll_events: EventReader<MouseWheel>,
+    mut selected_equipment: ResMut<SelectedEquipment>,
+) {
+    let steps = scroll_events
+        .read()
+        .map(|event| event.y.signum() as i32)
+        .sum::<i32>();
+    if steps == 0 || zoom_modifier_pressed(&keyboard_input) {
+        return;
+    }
+
+    let placeable = EquipmentKind::ALL
+        .into_iter()
+        .filter(|kind| kind.is_placeable())
+        .collect::<Vec<EquipmentKind>>();
+    let current = placeable
+        .iter()
+        .position(|kind| *kind == selected_equipment.kind)
+        .unwrap_or(0) as i32;
+
+    selected_equipment.kind = placeable[(current + steps).rem_euclid(placeable.len() as i32) as usize];
+}
+
 pub fn define_cursor_mode(
     mut commands: Commands
 ) {
@@ -93,12 +148,32 @@ pub fn define_cursor_mode(
                 font_size: 60.0,
                 color: get_colour(GamePallete::JapaneseIndigo),
             },
-        )]),
+        ),
+        TextSection::new(
+            "\nSelected:  ",
+            TextStyle {
+                font: font_handle.clone(),
+                font_size: 60.0,
+                color: get_colour(GamePallete::Feldgrau),
+            },
+        ),
+        TextSection::new(
+            "",
+            TextStyle {
+                font: font_handle.clone(),
+                font_size: 60.0,
+                color: get_colour(GamePallete::JapaneseIndigo),
+            },
+        )]).with_style(hud_style(HudCorner::TopLeft)),
         CursorModeText
     ));
 }
 
 fn render_cursor_mode_text(
+    game_settings: Res<GameSettings>,
+    grid: Res<Grid>,
+    cursor_idx: Res<CursorGridIdx>,
+    selected_equipment: Res<SelectedEquipment>,
     q_cursor: Query<(Entity, &Cursor, Option<&PlacingComponents>, Option<&DeletingComponents>)>,
     mut q_cursor_mode_text: Query<&mut Text, With<CursorModeText>>,
 ) {
@@ -109,8 +184,19 @@ fn render_cursor_mode_text(
         (Err(_), Err(_)) => "None",
         (Ok(_), Ok(_)) => panic!("Cursor is in both placing and deleting mode!"),
     };
+    // priced for the terrain under the cursor, the same as placing it there would charge
+    let terrain = cursor_idx.index
+        .and_then(|cell_idx| grid.cells.get(&cell_idx))
+        .map(|cell| cell.terrain)
+        .unwrap_or_default();
+    let selected = format!(
+        "{} ({})",
+        selected_equipment.kind.label(),
+        placement_cost(selected_equipment.kind, terrain, &game_settings)
+    );
     for mut text in &mut q_cursor_mode_text {
         text.sections[1].value = mode.to_string();
+        text.sections[3].value = selected.clone();
     }
 }
 
@@ -0,0 +1,103 @@
+use bevy::{
+    prelude::*,
+    sprite::{SpriteBundle, Sprite},
+};
+
+use crate::utils::{
+    assets::{AssetHandles, AssetSprite, get_asset},
+    game_settings::GameSettings,
+};
+use crate::game::grid::{Grid, GridSettings, CellId};
+use crate::AppState;
+pub use crate::flowy_core::equipment::FixtureMeta;
+
+
+pub struct FixturePlugin;
+
+impl Plugin for FixturePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Game), spawn_fixtures)
+            .add_systems(OnExit(AppState::Game), cleanup_fixtures);
+    }
+}
+
+
+/// Equipment the level put on the map, which the player can't delete
+#[derive(Component)]
+pub struct Locked;
+
+
+/// A pump station, home or business placed by the level
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Fixture {
+    pub cell_idx: CellId,
+    pub meta: FixtureMeta,
+}
+
+
+/// Spawn a locked fixture in every cell the level put a pump station, home or business in, and mark the cell as
+/// occupied by it
+pub fn spawn_fixtures(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    asset_server: Res<AssetServer>,
+    asset_handles: Res<AssetHandles>,
+    grid_settings: Res<GridSettings>,
+    mut grid: ResMut<Grid>,
+) {
+    let cell_size = Vec2::new(grid_settings.cell_width as f32, grid_settings.cell_height as f32);
+
+    for cell in grid.cells.values_mut() {
+        let Some(feature) = cell.feature else {
+            continue;
+        };
+        let Some((kind, meta)) = FixtureMeta::for_feature(feature, &game_settings.hydraulics) else {
+            continue;
+        };
+
+        let world_position = grid_settings.cell_to_world(&cell.centre);
+        let sprite = match get_asset(kind, &game_settings.assets, &asset_handles, &asset_server) {
+            AssetSprite::Texture(texture) => SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(cell_size),
+                    ..default()
+                },
+                texture,
+                ..default()
+            },
+            AssetSprite::Colour(colour) => SpriteBundle {
+                sprite: Sprite {
+                    // a little smaller than the cell so the building plot shows round the edge
+                    custom_size: Some(cell_size * 0.8),
+                    color: colour,
+                    ..default()
+                },
+                ..default()
+            },
+        };
+
+        let entity = commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(world_position.x, world_position.y, 0.0),
+                ..sprite
+            },
+            Fixture { cell_idx: cell.id, meta },
+            kind,
+            Locked,
+        )).id();
+
+        cell.occupied = true;
+        cell.occupied_by = Some(entity.to_bits());
+    }
+}
+
+
+fn cleanup_fixtures(
+    mut commands: Commands,
+    q_fixtures: Query<Entity, With<Fixture>>,
+) {
+    for entity in q_fixtures.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::{
+    prelude::*,
+    text::Text2dBundle,
+};
+
+use crate::utils::{
+    assets::{AssetHandles, get_asset},
+    colours::{GamePallete, get_colour},
+    game_settings::GameSettings,
+};
+use crate::game::grid::{Grid, GridSettings, CellId, CursorGridIdx, level::Level};
+use crate::game::cursor::{Cursor, DeletingComponents, PlacingComponents, SelectedEquipment};
+use crate::game::history::Edit;
+use crate::flowy_core::equipment::Placement;
+use crate::AppState;
+use super::{
+    Budget, BudgetedEquipment, Equipment, InsufficientFunds, SpawnedEquipment, ToBeDespawned,
+    available_budget, existing_equipment, flag_equipment, insert_equipment_sprite, placement_cost,
+};
+use super::routing::lay_equipment;
+use super::kind::{EquipmentKind, Rotation};
+
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, update_ghost
+                .after(flag_equipment)
+                .run_if(in_state(AppState::Game))
+                .run_if(resource_changed::<CursorGridIdx>()
+                    .or_else(resource_changed::<SelectedEquipment>())
+                    .or_else(resource_changed::<InsufficientFunds>())
+                    .or_else(resource_changed::<Budget>())
+                    .or_else(cursor_or_equipment_changed)))
+            .add_systems(OnExit(AppState::Game), cleanup_ghost);
+    }
+}
+
+
+/// How see through the ghost is, so the cell underneath still shows
+const GHOST_ALPHA: f32 = 0.5;
+
+
+type CursorChangedFilter = Or<(Changed<Cursor>, Added<PlacingComponents>, Added<DeletingComponents>)>;
+
+
+/// A see through piece of equipment showing what would be laid in a cell and what it would cost
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct Ghost {
+    pub cell_idx: CellId,
+    pub kind: EquipmentKind,
+    pub rotation: Rotation,
+    pub cost: i32,
+    pub placement: Placement,
+}
+
+
+/// Whether the cursor has changed mode or moved along its path, or equipment has been drawn or taken off the grid,
+/// any of which can change what the ghost shows
+fn cursor_or_equipment_changed(
+    q_cursor: Query<(), CursorChangedFilter>,
+    q_new_equipment: Query<(), Added<SpawnedEquipment>>,
+    mut removed_placing: RemovedComponents<PlacingComponents>,
+    mut removed_deleting: RemovedComponents<DeletingComponents>,
+    mut removed_equipment: RemovedComponents<SpawnedEquipment>,
+) -> bool {
+    // read every reader so old removals aren't seen again next frame
+    let removed = removed_placing.read().count() + removed_deleting.read().count() + removed_equipment.read().count();
+    removed > 0 || !q_cursor.is_empty() || !q_new_equipment.is_empty()
+}
+
+
+/// Preview the selected equipment in the cell under the cursor, as it would be laid there, tinted green if it can be
+/// laid and red if the cell is taken, the terrain can't be built on or there isn't enough money. While dragging, the
+/// piece just laid under the cursor is drawn for real, so the ghost shows in the cells the drag couldn't lay in,
+/// joined on to where the drag came from. It is hidden while deleting.
+/// Only worked out again when something it depends on changes, and only redrawn when what it shows changes.
+#[allow(clippy::too_many_arguments)]
+fn update_ghost(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    asset_server: Res<AssetServer>,
+    asset_handles: Res<AssetHandles>,
+    level: Res<Level>,
+    budget: Res<Budget>,
+    grid: Res<Grid>,
+    grid_settings: Res<GridSettings>,
+    cursor_idx: Res<CursorGridIdx>,
+    selected_equipment: Res<SelectedEquipment>,
+    insufficient_funds: Res<InsufficientFunds>,
+    q_cursor: Query<(&Cursor, Option<&PlacingComponents>, Option<&DeletingComponents>)>,
+    q_existing_equipment: Query<(Entity, &Equipment, &EquipmentKind), With<SpawnedEquipment>>,
+    q_pending: Query<(&Equipment, Has<BudgetedEquipment>, Has<ToBeDespawned>)>,
+    q_ghost: Query<(Entity, &Ghost)>,
+) {
+    let Ok((cursor, placing, deleting)) = q_cursor.get_single() else {
+        return;
+    };
+
+    let wanted = match (insufficient_funds.blocked, cursor_idx.index) {
+        (Some((cell_idx, kind, rotation)), _) => grid.cells.get(&cell_idx).map(|cell| Ghost {
+            cell_idx,
+            kind,
+            rotation,
+            cost: placement_cost(kind, cell.terrain, &game_settings),
+            placement: Placement::Unaffordable,
+        }),
+        (None, Some(cell_idx)) if deleting.is_none() => grid.cells.get(&cell_idx).and_then(|cell| {
+            let previous_idx = match placing {
+                Some(_) if cell.occupied && cell.feature.is_none() => return None,
+                Some(_) => cursor.path.iter().rev().find(|path_idx| **path_idx != cell_idx).copied(),
+                None => None,
+            };
+            // the piece that would be laid here, which is the selected one laid unrotated if nothing can go in
+            let existing = existing_equipment(&q_existing_equipment, &grid, &game_settings);
+            let (kind, rotation) = lay_equipment(&grid, &game_settings, &existing, selected_equipment.kind, previous_idx, cell_idx)
+                .into_iter()
+                .find_map(|edit| match edit {
+                    Edit::Spawned(spawned_idx, record) if spawned_idx == cell_idx => Some((record.kind, record.rotation)),
+                    _ => None,
+                })
+                .unwrap_or((selected_equipment.kind, Rotation::default()));
+            let cost = placement_cost(kind, cell.terrain, &game_settings);
+            let available = available_budget(&budget, &q_pending);
+
+            Some(Ghost {
+                cell_idx,
+                kind,
+                rotation,
+                cost,
+                placement: Placement::check(cell, cost, available, level.budget_mode, &game_settings),
+            })
+        }),
+        _ => None,
+    };
+
+    let current = q_ghost.get_single().ok();
+    if current.map(|(_, ghost)| ghost) == wanted.as_ref() {
+        return;
+    }
+
+    if let Some((entity, _)) = current {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(ghost) = wanted else {
+        return;
+    };
+    let Some(cell) = grid.cells.get(&ghost.cell_idx) else {
+        return;
+    };
+
+    let world_position = grid_settings.cell_to_world(&cell.centre);
+    let cell_size = Vec2::new(grid_settings.cell_width as f32, grid_settings.cell_height as f32);
+    // just in front of the equipment
+    let transform = Transform::from_xyz(world_position.x, world_position.y, 1.0);
+    let sprite = get_asset(ghost.kind, &game_settings.assets, &asset_handles, &asset_server);
+    let tint = if ghost.placement.is_placeable() {
+        Color::GREEN
+    } else {
+        Color::RED
+    };
+
+    let mut entity_commands = commands.spawn_empty();
+    insert_equipment_sprite(&mut entity_commands, ghost.kind, ghost.rotation, sprite, cell_size, transform, Some(tint.with_a(GHOST_ALPHA)));
+    entity_commands.with_children(|parent| {
+        parent.spawn(Text2dBundle {
+            text: Text::from_section(
+                ghost.cost.to_string(),
+                TextStyle {
+                    font: Default::default(),
+                    font_size: cell_size.y / 3.0,
+                    color: get_colour(GamePallete::JapaneseIndigo),
+                },
+            ),
+            // in the top corner of the cell, clear of the pipe running through the middle
+            transform: Transform::from_xyz(cell_size.x / 3.0, cell_size.y / 3.0, 0.1),
+            ..default()
+        });
+    });
+    entity_commands.insert(ghost);
+}
+
+
+fn cleanup_ghost(
+    mut commands: Commands,
+    q_ghost: Query<Entity, With<Ghost>>,
+) {
+    for entity in q_ghost.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,11 @@
+use bevy::{
+    prelude::*,
+    ecs::component::TableStorage,
+};
+
+pub use crate::flowy_core::equipment::{EquipmentKind, Rotation};
+
+
+impl Component for EquipmentKind {
+    type Storage = TableStorage;
+}
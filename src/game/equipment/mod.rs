@@ -0,0 +1,488 @@
+pub mod kind;
+pub mod fixture;
+mod routing;
+mod ghost;
+
+use bevy::{
+    prelude::*,
+    ecs::system::EntityCommands,
+    sprite::{SpriteBundle, Sprite},
+};
+
+use crate::utils::{
+    assets::{AssetHandles, AssetSprite, get_asset},
+    colours::{GamePallete, get_colour},
+    game_settings::GameSettings,
+    ui::{HudCorner, hud_style},
+};
+use crate::game::grid::{GridSettings, Grid, CellId, CursorGridIdx, Direction, level::{Level, Feature}};
+use crate::flowy_core::budget::BudgetMode;
+use kind::{EquipmentKind, Rotation};
+use fixture::{FixturePlugin, Locked};
+pub use crate::flowy_core::{budget::Budget, equipment::placement_cost};
+use crate::flowy_core::ledger::{Ledger, LedgerEntry, Transaction};
+use routing::{EquipmentLookup, lay_equipment, apply_edits, edits_cost};
+use ghost::GhostPlugin;
+pub use ghost::Ghost;
+use crate::game::cursor::{Cursor, DeletingComponents, PlacingComponents, SelectedEquipment};
+use crate::game::history::{History, Edit, EquipmentRecord};
+use crate::game::timer::GameTimer;
+use crate::AppState;
+use crate::game::SimulationState;
+
+
+
+pub struct EquipmentPlugin;
+
+impl Plugin for EquipmentPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Budget>()
+            .init_resource::<InsufficientFunds>()
+            .add_plugins((FixturePlugin, GhostPlugin))
+            .add_systems(OnEnter(AppState::Game), define_budget)
+            .add_systems(Update, (flag_equipment, update_budget, render_budget, despawn_equipment)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running))
+                .chain())
+            // drawn even while paused, so a network restored from a save shows up before the player resumes
+            .add_systems(Update, spawn_equipment
+                .after(flag_equipment)
+                .before(update_budget)
+                .run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), (cleanup_equipment, cleanup_budget));
+    }
+}
+
+
+#[derive(Component, Debug)]
+pub struct Equipment {
+    pub cell_idx: CellId,
+    pub rotation: Rotation,
+    pub cost: Option<i32>
+}
+
+#[derive(Component)]
+pub struct SpawnedEquipment;
+
+#[derive(Component)]
+pub struct ToBeSpawned;
+
+#[derive(Component)]
+pub struct ToBeDespawned;
+
+
+impl Resource for Budget {}
+
+type NewEquipmentFilter = (With<SpawnedEquipment>, Without<BudgetedEquipment>);
+type DeletedEquipmentFilter = (With<BudgetedEquipment>, With<ToBeDespawned>);
+
+/// Identifies the budget text
+#[derive(Component, Default)]
+pub struct BudgetText; 
+
+/// Defines the equipment which has already been accounted for in the budget
+#[derive(Component, Default)]
+pub struct BudgetedEquipment;
+
+
+/// How long the insufficient funds message stays up for
+const FUNDS_MESSAGE_SECS: f32 = 2.0;
+
+/// The last placement that was blocked for costing more than was left in a hard capped budget
+#[derive(Resource, Default, Debug)]
+pub struct InsufficientFunds {
+    /// The cell the placement was blocked in and the piece that would have been laid there, drawn as a red ghost
+    /// until the cursor leaves the cell
+    pub blocked: Option<(CellId, EquipmentKind, Rotation)>,
+    /// Seconds left showing the message under the budget
+    pub message_secs: f32,
+}
+
+
+/// The budget once everything flagged but not yet settled by update_budget has been charged or refunded
+fn available_budget(budget: &Budget, q_pending: &Query<(&Equipment, Has<BudgetedEquipment>, Has<ToBeDespawned>)>) -> Budget {
+    let mut available = *budget;
+    for (equipment, budgeted, despawning) in q_pending.iter() {
+        let cost = equipment.cost.unwrap_or_default();
+        match (budgeted, despawning) {
+            (false, false) => available.charge(cost),
+            (true, true) => available.refund(cost),
+            _ => {},
+        }
+    }
+    available
+}
+
+
+/// If you are in placement mode, equipment is laid in the cells your mouse drags across and flagged to be spawned.
+/// When the level has a hard capped budget anything that costs more than is left is blocked instead.
+/// If you are in deletion mode, the cells your mouse moves over are toggled to unoccupied and entity is flagged to be despawned.
+/// Everything done between entering and leaving a mode is recorded in the history as a single stroke.
+#[allow(clippy::too_many_arguments)]
+pub fn flag_equipment(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    level: Res<Level>,
+    budget: Res<Budget>,
+    cursor_idx: Res<CursorGridIdx>,
+    selected_equipment: Res<SelectedEquipment>,
+    mut grid: ResMut<Grid>,
+    mut history: ResMut<History>,
+    mut insufficient_funds: ResMut<InsufficientFunds>,
+    mut q_cursor: Query<(&mut Cursor, Option<&PlacingComponents>, Option<&DeletingComponents>)>,
+    q_existing_equipment: Query<(Entity, &Equipment, &EquipmentKind), With<SpawnedEquipment>>,
+    q_pending: Query<(&Equipment, Has<BudgetedEquipment>, Has<ToBeDespawned>)>,
+    q_locked: Query<(), With<Locked>>,
+) {
+    let (mut cursor, placing, deleting) = q_cursor.single_mut();
+    // only written when there is something to clear, so the ghost isn't redrawn every frame
+    if placing.is_none() && !cursor.path.is_empty() {
+        cursor.path.clear();
+    }
+    if insufficient_funds.blocked.is_some_and(|(cell_idx, _, _)| placing.is_none() || cursor_idx.index != Some(cell_idx)) {
+        insufficient_funds.blocked = None;
+    }
+    if placing.is_none() && deleting.is_none() {
+        history.end_stroke();
+    }
+
+    // the index can briefly point at a cell of the previous level, so only act on cells this grid has
+    if let Some(cursor_index) = cursor_idx.index.filter(|cell_idx| grid.cells.get(cell_idx).is_some()) {
+        // only lay equipment as the cursor moves into a new cell
+        if placing.is_some() && cursor.path.last() != Some(&cursor_index) {
+            let previous_index = cursor.path.last().copied();
+            cursor.path.push(cursor_index);
+
+            let existing = existing_equipment(&q_existing_equipment, &grid, &game_settings);
+            let edits = lay_equipment(&grid, &game_settings, &existing, selected_equipment.kind, previous_index, cursor_index);
+
+            let cost = edits_cost(&edits);
+            if level.budget_mode == BudgetMode::HardCap && !available_budget(&budget, &q_pending).can_afford(cost) {
+                // show the piece that would have gone in the cell under the cursor
+                insufficient_funds.blocked = edits
+                    .iter()
+                    .find_map(|edit| match edit {
+                        Edit::Spawned(cell_idx, record) if *cell_idx == cursor_index => Some((*cell_idx, record.kind, record.rotation)),
+                        _ => None,
+                    });
+                insufficient_funds.message_secs = FUNDS_MESSAGE_SECS;
+            } else {
+                apply_edits(&mut commands, &mut grid, &existing, &edits);
+                for edit in edits {
+                    history.record(edit);
+                }
+            }
+        }
+
+        let Some(current_cell) = grid.cells.get_mut(&cursor_index) else { return };
+
+        // blocked cells and the fixtures the level placed are part of the map and can't be removed
+        let locked = current_cell.feature == Some(Feature::Blocked)
+            || current_cell.occupied_by.is_some_and(|bits| q_locked.contains(Entity::from_bits(bits)));
+        if deleting.is_some() && current_cell.occupied && !locked {
+            current_cell.occupied = false;
+
+            for (entity, equipment, kind) in q_existing_equipment.iter() {
+                if equipment.cell_idx == cursor_index {
+                    flag_to_despawn(&mut commands, entity);
+                    history.record(Edit::Despawned(cursor_index, EquipmentRecord {
+                        kind: *kind,
+                        rotation: equipment.rotation,
+                        cost: equipment_cost(equipment, *kind, &grid, &game_settings),
+                    }));
+                }
+            }
+        }     
+    }
+}
+
+
+/// The equipment on the grid keyed by its cell, for working out what laying more would change
+fn existing_equipment(
+    q_existing_equipment: &Query<(Entity, &Equipment, &EquipmentKind), With<SpawnedEquipment>>,
+    grid: &Grid,
+    game_settings: &GameSettings,
+) -> EquipmentLookup {
+    q_existing_equipment
+        .iter()
+        .map(|(entity, equipment, kind)| {
+            let cost = equipment_cost(equipment, *kind, grid, game_settings);
+            (equipment.cell_idx, (entity, *kind, equipment.rotation, cost))
+        })
+        .collect()
+}
+
+
+/// Mark a cell as occupied and flag a new piece of equipment to be spawned in it, to be charged the given cost
+pub fn flag_to_spawn(commands: &mut Commands, grid: &mut Grid, cell_idx: CellId, kind: EquipmentKind, rotation: Rotation, cost: i32) {
+    if let Some(cell) = grid.cells.get_mut(&cell_idx) {
+        cell.occupied = true;
+    }
+
+    commands.spawn(
+        (
+            Equipment {
+                cell_idx,
+                rotation,
+                cost: Some(cost)
+            },
+            kind,
+            ToBeSpawned,
+        )
+    );
+}
+
+
+/// Flag a piece of equipment to be despawned, it is refunded by update_budget
+pub fn flag_to_despawn(commands: &mut Commands, entity: Entity) {
+    commands.entity(entity)
+        .insert(ToBeDespawned)
+        .remove::<SpawnedEquipment>();
+}
+
+/// Draw equipment flagged to be spawned with its texture, turned to its rotation.
+/// Equipment without a texture is drawn in its colour as a hub with a length of pipe out to each open port.
+pub fn spawn_equipment(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    asset_server: Res<AssetServer>,
+    asset_handles: Res<AssetHandles>,
+    grid: Res<Grid>,
+    grid_settings: Res<GridSettings>,
+    q_equipment_to_spawn: Query<(Entity, &Equipment, &EquipmentKind), With<ToBeSpawned>>,
+) {
+    for (entity, equipment, kind) in q_equipment_to_spawn.iter() {
+        let cell_idx = equipment.cell_idx;
+
+        let current_cell = grid.cells.get(&cell_idx).unwrap();
+        let world_position = grid_settings.cell_to_world(&current_cell.centre);
+        let cell_size = Vec2::new(grid_settings.cell_width as f32, grid_settings.cell_height as f32);
+        let transform = Transform::from_xyz(world_position.x, world_position.y, 0.0);
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands
+            .remove::<ToBeSpawned>()
+            .insert(SpawnedEquipment);
+
+        let sprite = get_asset(*kind, &game_settings.assets, &asset_handles, &asset_server);
+        insert_equipment_sprite(&mut entity_commands, *kind, equipment.rotation, sprite, cell_size, transform, None);
+    }
+}
+
+
+/// Draw a piece of equipment with its texture turned to its rotation, or in its colour as a hub with a length of
+/// pipe out to each open port. A tint is blended over the texture or drawn instead of the colour.
+pub fn insert_equipment_sprite(
+    entity_commands: &mut EntityCommands,
+    kind: EquipmentKind,
+    rotation: Rotation,
+    sprite: AssetSprite,
+    cell_size: Vec2,
+    transform: Transform,
+    tint: Option<Color>,
+) {
+    match sprite {
+        AssetSprite::Texture(texture) => {
+            entity_commands.insert(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(cell_size),
+                    color: tint.unwrap_or(Color::WHITE),
+                    ..default()
+                },
+                texture,
+                transform: transform.with_rotation(Quat::from_rotation_z(rotation.angle())),
+                ..default()
+            });
+        },
+        AssetSprite::Colour(colour) => {
+            let colour = tint.unwrap_or(colour);
+            let hub_scale = match kind {
+                EquipmentKind::Pump => 0.6,
+                _ => PIPE_WIDTH,
+            };
+
+            entity_commands
+                .insert(SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(cell_size * hub_scale),
+                        color: colour,
+                        ..default()
+                    },
+                    transform,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for direction in kind.open_ports(rotation) {
+                        parent.spawn(port_sprite(direction, cell_size, colour));
+                    }
+                });
+        },
+    }
+}
+
+
+/// The width of a pipe as a fraction of the cell size
+const PIPE_WIDTH: f32 = 1.0 / 3.0;
+
+/// A length of pipe running from the centre of the cell out to the side of an open port
+fn port_sprite(direction: Direction, cell_size: Vec2, colour: Color) -> SpriteBundle {
+    let (size, offset) = match direction {
+        Direction::North => (Vec2::new(cell_size.x * PIPE_WIDTH, cell_size.y / 2.0), Vec2::new(0.0, cell_size.y / 4.0)),
+        Direction::East => (Vec2::new(cell_size.x / 2.0, cell_size.y * PIPE_WIDTH), Vec2::new(cell_size.x / 4.0, 0.0)),
+        Direction::South => (Vec2::new(cell_size.x * PIPE_WIDTH, cell_size.y / 2.0), Vec2::new(0.0, -cell_size.y / 4.0)),
+        Direction::West => (Vec2::new(cell_size.x / 2.0, cell_size.y * PIPE_WIDTH), Vec2::new(-cell_size.x / 4.0, 0.0)),
+    };
+
+    SpriteBundle {
+        sprite: Sprite {
+            custom_size: Some(size),
+            color: colour,
+            ..default()
+        },
+        // sit just behind the hub so the pump body is drawn over its ports
+        transform: Transform::from_xyz(offset.x, offset.y, -0.1),
+        ..default()
+    }
+}
+
+pub fn despawn_equipment(
+    mut commands: Commands,
+    q_equipment_to_despawn: Query<(Entity, &Equipment), With<ToBeDespawned>>,
+) {
+    for (entity, _) in q_equipment_to_despawn.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn define_budget(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut budget: ResMut<Budget>,
+    mut insufficient_funds: ResMut<InsufficientFunds>,
+) {
+    let font_handle: Handle<Font> = Default::default();
+    budget.0 = level.start_budget;
+    *insufficient_funds = InsufficientFunds::default();
+
+    commands.spawn((TextBundle::from_sections([
+        TextSection::new(
+            "Budget:  ",
+            TextStyle {
+                font: font_handle.clone(),
+                font_size: 60.0,
+                color: get_colour(GamePallete::Feldgrau),
+            },
+        ),
+        TextSection::new(
+            budget.0.to_string(),
+            TextStyle {
+                font: font_handle.clone(),
+                font_size: 60.0,
+                color: get_colour(GamePallete::JapaneseIndigo),
+            },
+        ),
+        TextSection::new(
+            "",
+            TextStyle {
+                font: font_handle.clone(),
+                font_size: 30.0,
+                color: get_colour(GamePallete::Feldgrau),
+            },
+        )]).with_style(hud_style(HudCorner::TopRight)),
+        BudgetText
+    ));
+}
+
+
+/// What a piece of equipment was charged, or would be charged if it hasn't been budgeted yet
+fn equipment_cost(equipment: &Equipment, kind: EquipmentKind, grid: &Grid, game_settings: &GameSettings) -> i32 {
+    equipment.cost.unwrap_or_else(|| {
+        let terrain = grid.cells
+            .get(&equipment.cell_idx)
+            .map(|cell| cell.terrain)
+            .unwrap_or_default();
+        placement_cost(kind, terrain, game_settings)
+    })
+}
+
+
+/// Charge for new equipment and refund deleted equipment, writing each one in the ledger
+#[allow(clippy::too_many_arguments)]
+pub fn update_budget(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    grid: Res<Grid>,
+    timer: Res<GameTimer>,
+    mut budget: ResMut<Budget>,
+    mut ledger: ResMut<Ledger>,
+    mut q_new_equipment: Query<(Entity, &mut Equipment, &EquipmentKind), NewEquipmentFilter>,
+    q_equipment_to_be_deleted: Query<(Entity, &Equipment, &EquipmentKind), DeletedEquipmentFilter>,
+) {
+    let ledger_entry = |transaction, equipment: &Equipment, kind: EquipmentKind, cost| {
+        let terrain = grid.cells
+            .get(&equipment.cell_idx)
+            .map(|cell| cell.terrain)
+            .unwrap_or_default();
+        LedgerEntry::new(transaction, kind, equipment.cell_idx, terrain, cost, timer.timer.elapsed(), &game_settings)
+    };
+
+    for (entity, mut equipment, kind) in q_new_equipment.iter_mut() {
+        let cost = equipment_cost(&equipment, *kind, &grid, &game_settings);
+
+        // remember what was charged so removing the equipment refunds exactly that
+        equipment.cost = Some(cost);
+        budget.charge(cost);
+        ledger.record(ledger_entry(Transaction::Charge, &equipment, *kind, cost));
+        commands.entity(entity).insert(BudgetedEquipment);
+    }
+    for (entity, equipment, kind) in q_equipment_to_be_deleted.iter() {
+        if let Some(cost) = equipment.cost {
+            budget.refund(cost);
+            ledger.record(ledger_entry(Transaction::Refund, equipment, *kind, cost));
+        }
+        commands.entity(entity).remove::<BudgetedEquipment>();
+    }
+}
+
+
+/// Show the budget, with a message underneath for a while after a placement is blocked for costing too much
+pub fn render_budget(
+    time: Res<Time>,
+    budget: Res<Budget>,
+    mut insufficient_funds: ResMut<InsufficientFunds>,
+    mut query: Query<&mut Text, With<BudgetText>>,
+) {
+    if insufficient_funds.message_secs > 0.0 {
+        insufficient_funds.message_secs = (insufficient_funds.message_secs - time.delta_seconds()).max(0.0);
+    }
+    let message = if insufficient_funds.message_secs > 0.0 {
+        "\nInsufficient funds"
+    } else {
+        ""
+    };
+
+    for mut text in &mut query {
+        text.sections[1].value = budget.0.to_string();
+        text.sections[2].value = message.to_string();
+    }
+}
+
+
+fn cleanup_equipment(
+    mut commands: Commands,
+    q_equipment: Query<Entity, With<Equipment>>,
+) {
+    for entity in q_equipment.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn cleanup_budget(
+    mut commands: Commands,
+    q_budget_text: Query<Entity, With<BudgetText>>,
+) {
+    if let Ok(entity) = q_budget_text.get_single() {
+        commands.entity(entity).despawn_recursive();
+    }
+}  
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::utils::game_settings::GameSettings;
+use crate::game::grid::{Grid, CellId, Direction, level::Feature};
+use crate::game::history::{Edit, EquipmentRecord};
+use super::{flag_to_spawn, flag_to_despawn, placement_cost};
+use super::kind::{EquipmentKind, Rotation};
+
+
+/// The equipment already on the grid, keyed by the cell it sits in, along with what it cost
+pub type EquipmentLookup = HashMap<CellId, (Entity, EquipmentKind, Rotation, i32)>;
+
+
+/// Plan laying equipment in the cell the cursor has just dragged into, joining it on to the cell it came from.
+/// The selected fitting is only laid in a cell on its own, along a path the fitting is picked from the sides it
+/// joins, so the fitting the cursor came from and any fitting it runs into are upgraded to take the new branch.
+/// Anything else is laid in line with the direction of travel.
+/// Nothing is changed until the returned pieces to spawn and despawn are passed to `apply_edits`, so they can be
+/// checked against the budget first.
+pub fn lay_equipment(
+    grid: &Grid,
+    game_settings: &GameSettings,
+    existing: &EquipmentLookup,
+    selected_kind: EquipmentKind,
+    previous_idx: Option<CellId>,
+    cell_idx: CellId,
+) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let cell = &grid.cells[&cell_idx];
+    let can_lay = cell.feature.is_none() && !cell.occupied && cell.terrain.settings(&game_settings.terrain).placeable;
+    let accepts_connection = match (cell.feature, existing.get(&cell_idx)) {
+        (Some(feature), _) => feature != Feature::Blocked,
+        (None, Some(_)) => true,
+        (None, None) => can_lay,
+    };
+
+    let joined_direction = previous_idx.filter(|_| accepts_connection).and_then(|previous_idx| {
+        let direction = direction_between(grid, previous_idx, cell_idx)?;
+        let previous_feature = grid.cells[&previous_idx].feature;
+
+        match (previous_feature, existing.get(&previous_idx)) {
+            (Some(Feature::Blocked), _) => None,
+            (Some(_), _) => Some(direction),
+            (None, Some((_, kind, _, _))) if kind.is_fitting() => {
+                let mut sides = joined_sides(grid, existing, previous_idx);
+                sides.push(direction);
+                replace_fitting(grid, game_settings, existing, selected_kind, previous_idx, &sides, &mut edits);
+                Some(direction)
+            },
+            (None, Some((_, kind, rotation, _))) => kind.open_ports(*rotation).contains(&direction).then_some(direction),
+            (None, None) => None,
+        }
+    });
+    let entry_side = joined_direction.map(|direction| direction.opposite());
+
+    let cell = &grid.cells[&cell_idx];
+    if cell.feature.is_some() {
+        return edits;
+    }
+
+    match existing.get(&cell_idx) {
+        Some((_, kind, _, _)) if kind.is_fitting() => {
+            if let Some(entry_side) = entry_side {
+                let mut sides = joined_sides(grid, existing, cell_idx);
+                sides.push(entry_side);
+                replace_fitting(grid, game_settings, existing, selected_kind, cell_idx, &sides, &mut edits);
+            }
+        },
+        Some(_) => {},
+        None if can_lay => {
+            let (kind, rotation) = if selected_kind.is_fitting() {
+                EquipmentKind::fitting_with(selected_kind, &entry_side.into_iter().collect::<Vec<Direction>>())
+            } else {
+                let rotation = entry_side
+                    .and_then(|side| Rotation::ALL.into_iter().find(|rotation| selected_kind.open_ports(*rotation).contains(&side)))
+                    .unwrap_or_default();
+                (selected_kind, rotation)
+            };
+            lay_new(grid, game_settings, cell_idx, kind, rotation, &mut edits);
+        },
+        None => {},
+    }
+
+    edits
+}
+
+
+/// Swap the fitting in a cell for one that opens on the given sides, if it isn't already that fitting
+#[allow(clippy::too_many_arguments)]
+fn replace_fitting(
+    grid: &Grid,
+    game_settings: &GameSettings,
+    existing: &EquipmentLookup,
+    selected_kind: EquipmentKind,
+    cell_idx: CellId,
+    sides: &[Direction],
+    edits: &mut Vec<Edit>,
+) {
+    let (kind, rotation) = EquipmentKind::fitting_with(selected_kind, sides);
+
+    if let Some((_, current_kind, current_rotation, current_cost)) = existing.get(&cell_idx) {
+        if (*current_kind, *current_rotation) == (kind, rotation) {
+            return;
+        }
+        edits.push(Edit::Despawned(cell_idx, EquipmentRecord { kind: *current_kind, rotation: *current_rotation, cost: *current_cost }));
+    }
+
+    lay_new(grid, game_settings, cell_idx, kind, rotation, edits);
+}
+
+
+fn lay_new(
+    grid: &Grid,
+    game_settings: &GameSettings,
+    cell_idx: CellId,
+    kind: EquipmentKind,
+    rotation: Rotation,
+    edits: &mut Vec<Edit>,
+) {
+    let cost = placement_cost(kind, grid.cells[&cell_idx].terrain, game_settings);
+    edits.push(Edit::Spawned(cell_idx, EquipmentRecord { kind, rotation, cost }));
+}
+
+
+/// What a set of planned edits would take out of the budget once anything they replace is refunded
+pub fn edits_cost(edits: &[Edit]) -> i32 {
+    edits
+        .iter()
+        .map(|edit| match edit {
+            Edit::Spawned(_, record) => record.cost,
+            Edit::Despawned(_, record) => -record.cost,
+        })
+        .sum()
+}
+
+
+/// Flag the pieces planned by `lay_equipment` to be spawned and despawned.
+/// The refund for anything replaced and the charge for what replaces it are settled by update_budget.
+pub fn apply_edits(commands: &mut Commands, grid: &mut Grid, existing: &EquipmentLookup, edits: &[Edit]) {
+    for edit in edits {
+        match edit {
+            Edit::Spawned(cell_idx, record) => flag_to_spawn(commands, grid, *cell_idx, record.kind, record.rotation, record.cost),
+            Edit::Despawned(cell_idx, _) => {
+                if let Some((entity, _, _, _)) = existing.get(cell_idx) {
+                    flag_to_despawn(commands, *entity);
+                }
+            },
+        }
+    }
+}
+
+
+/// The open ports of the equipment in a cell that something is actually joined on to
+fn joined_sides(grid: &Grid, existing: &EquipmentLookup, cell_idx: CellId) -> Vec<Direction> {
+    let Some((_, kind, rotation, _)) = existing.get(&cell_idx) else {
+        return Vec::new();
+    };
+    let cell = &grid.cells[&cell_idx];
+
+    kind.open_ports(*rotation)
+        .into_iter()
+        .filter(|side| {
+            let Some(neighbour_idx) = cell.neighbours.get(*side) else {
+                return false;
+            };
+            match (grid.cells[&neighbour_idx].feature, existing.get(&neighbour_idx)) {
+                (Some(Feature::Blocked), _) => false,
+                (Some(_), _) => true,
+                (None, Some((_, neighbour_kind, neighbour_rotation, _))) => neighbour_kind
+                    .open_ports(*neighbour_rotation)
+                    .contains(&side.opposite()),
+                (None, None) => false,
+            }
+        })
+        .collect()
+}
+
+
+/// The side of the `from` cell that the `to` cell is on, if they are neighbours
+fn direction_between(grid: &Grid, from: CellId, to: CellId) -> Option<Direction> {
+    let from_cell = grid.cells.get(&from)?;
+    Direction::ALL
+        .into_iter()
+        .find(|direction| from_cell.neighbours.get(*direction) == Some(to))
+}
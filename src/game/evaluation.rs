@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+use crate::game::{GameOver, SimulationState};
+use crate::game::equipment::Budget;
+use crate::game::grid::level::Level;
+use crate::game::hydraulics::HydraulicState;
+use crate::game::timer::GameTimer;
+use crate::game::ledger::Ledger;
+use crate::flowy_core::budget::{BudgetMode, level_score};
+use crate::utils::game_settings::GameSettings;
+use crate::AppState;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelOutcome {
+    AllSupplied,
+    OutOfTime,
+    OverBudget,
+}
+
+impl LevelOutcome {
+    pub fn is_win(&self) -> bool {
+        matches!(self, LevelOutcome::AllSupplied)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            LevelOutcome::AllSupplied => "Level Complete!",
+            LevelOutcome::OutOfTime => "Out of Time",
+            LevelOutcome::OverBudget => "Over Budget",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            LevelOutcome::AllSupplied => "Every home and business has enough water for a cup of tea.",
+            LevelOutcome::OutOfTime => "The timer ran out before everyone was supplied.",
+            LevelOutcome::OverBudget => "The network cost more than the budget allowed.",
+        }
+    }
+}
+
+
+/// How the last level ended, shown on the game over screen
+#[derive(Resource, Default, Debug)]
+pub struct LevelResult {
+    pub outcome: Option<LevelOutcome>,
+    pub level_name: String,
+    pub money_spent: i32,
+    pub time_left: f32,
+    /// How far below zero a level in overdraft mode ended
+    pub overdraft: i32,
+    pub score: i32,
+    /// Every charge and refund made during the level, for the invoice
+    pub ledger: Ledger,
+}
+
+
+/// End the level as a win once every consumer is supplied, or as a loss if a hard capped budget has gone negative.
+/// A level in overdraft mode carries on below zero and pays for it in the score instead.
+/// Running out of time is reported by the timer itself.
+pub fn evaluate_level(
+    level: Res<Level>,
+    budget: Res<Budget>,
+    hydraulic_state: Res<HydraulicState>,
+    mut game_over_writer: EventWriter<GameOver>,
+) {
+    if !hydraulic_state.supplied_consumers.is_empty() && hydraulic_state.all_consumers_supplied() {
+        game_over_writer.send(GameOver { outcome: LevelOutcome::AllSupplied });
+    } else if level.budget_mode == BudgetMode::HardCap && budget.is_overdrawn() {
+        game_over_writer.send(GameOver { outcome: LevelOutcome::OverBudget });
+    }
+}
+
+
+#[allow(clippy::too_many_arguments)]
+pub fn end_level(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    mut game_over_reader: EventReader<GameOver>,
+    budget: Res<Budget>,
+    ledger: Res<Ledger>,
+    level: Res<Level>,
+    timer: Res<GameTimer>,
+    mut level_result: ResMut<LevelResult>,
+) {
+    // a win takes priority if several things happen on the same frame
+    let Some(outcome) = game_over_reader
+        .read()
+        .map(|game_over| game_over.outcome)
+        .max_by_key(|outcome| outcome.is_win()) else {
+        return;
+    };
+
+    let time_left = timer.timer.remaining_secs();
+    *level_result = LevelResult {
+        outcome: Some(outcome),
+        level_name: level.name.clone(),
+        money_spent: level.start_budget - budget.0,
+        time_left,
+        overdraft: budget.overdraft(),
+        score: level_score(outcome.is_win(), *budget, time_left, game_settings.gameplay.overdraft_penalty),
+        ledger: ledger.clone(),
+    };
+
+    println!("Level over: {:?}", outcome);
+    commands.insert_resource(NextState(Some(SimulationState::Paused)));
+    commands.insert_resource(NextState(Some(AppState::GameOver)));
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::game::grid::{Grid, CellId, Direction};
+use crate::game::equipment::{Equipment, SpawnedEquipment, despawn_equipment, kind::EquipmentKind};
+use crate::AppState;
+use crate::game::SimulationState;
+pub use crate::flowy_core::flow::{FlowNetwork, solve_flow, equipment_ports};
+
+
+pub struct FlowPlugin;
+
+impl Plugin for FlowPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<FlowNetwork>()
+            .add_systems(OnEnter(AppState::Game), update_flow_network)
+            .add_systems(Update, update_flow_network
+                .after(despawn_equipment)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running))
+                .run_if(equipment_changed))
+            .add_systems(OnExit(AppState::Game), cleanup_flow_network);
+    }
+}
+
+
+impl Resource for FlowNetwork {}
+
+
+/// The open ports of each piece of equipment keyed by the cell it sits in
+pub fn collect_equipment_ports<'a>(equipment: impl Iterator<Item = (&'a Equipment, &'a EquipmentKind)>) -> HashMap<CellId, Vec<Direction>> {
+    equipment_ports(equipment.map(|(equipment, kind)| (equipment.cell_idx, *kind, equipment.rotation)))
+}
+
+
+fn equipment_changed(
+    q_new_equipment: Query<(), Added<SpawnedEquipment>>,
+    mut removed_equipment: RemovedComponents<SpawnedEquipment>,
+) -> bool {
+    // read every removal so the events don't trigger another update next frame
+    let removed = removed_equipment.read().count() > 0;
+    removed || !q_new_equipment.is_empty()
+}
+
+
+/// A one line summary of the network, printed when it changes
+fn network_summary(flow_network: &FlowNetwork) -> String {
+    format!(
+        "Network updated: {}/{} consumers connected, {} dead ends, {} leaks",
+        flow_network.connected_consumers.len(),
+        flow_network.connected_consumers.len() + flow_network.disconnected_consumers.len(),
+        flow_network.dead_ends.len(),
+        flow_network.leaks.len()
+    )
+}
+
+
+pub fn update_flow_network(
+    grid: Res<Grid>,
+    mut flow_network: ResMut<FlowNetwork>,
+    q_equipment: Query<(&Equipment, &EquipmentKind), With<SpawnedEquipment>>,
+) {
+    let equipment_ports = collect_equipment_ports(q_equipment.iter());
+
+    let previous_summary = network_summary(&flow_network);
+    *flow_network = solve_flow(&grid, &equipment_ports);
+
+    // most edits don't change the outcome, so only say something when they do
+    let summary = network_summary(&flow_network);
+    if summary != previous_summary {
+        println!("{}", summary);
+    }
+}
+
+
+fn cleanup_flow_network(
+    mut flow_network: ResMut<FlowNetwork>,
+) {
+    *flow_network = FlowNetwork::default();
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::utils::game_settings::GameSettings;
+pub use crate::flowy_core::level::*;
+
+
+/// The id of the level to load when entering the game. This matches a key of the `[levels]` table in the settings.
+/// If no level has been selected the first level in the settings is used.
+#[derive(Resource, Default, Debug)]
+pub struct SelectedLevel {
+    pub id: Option<String>
+}
+
+
+impl Resource for Level {}
+
+
+/// Load the selected level, falling back to a blank board the size of the window if it can't be loaded.
+pub fn load_level(
+    game_settings: Res<GameSettings>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut level: ResMut<Level>,
+) {
+    if selected_level.id.is_none() {
+        selected_level.id = game_settings.levels.keys().next().cloned();
+    }
+
+    let blank_level = || Level::blank(
+        game_settings.window.resolution.width / game_settings.grid.cell_width,
+        game_settings.window.resolution.height / game_settings.grid.cell_height,
+        game_settings.gameplay.budget
+    );
+
+    let selected = selected_level.id
+        .as_ref()
+        .and_then(|id| game_settings.levels.get(id).map(|level_meta| (id, level_meta)));
+
+    *level = match selected {
+        Some((id, level_meta)) => match Level::load(id, level_meta)
+            .and_then(|loaded_level| {
+                loaded_level.check_fits(game_settings.grid.cell_width, game_settings.grid.cell_height)?;
+                Ok(loaded_level)
+            }) {
+            Ok(loaded_level) => {
+                println!("Loaded level {}: {}", id, loaded_level.name);
+                loaded_level
+            },
+            Err(err) => {
+                println!("Failed to load level {} from {}: {}", id, level_meta.path, err);
+                blank_level()
+            }
+        },
+        None => blank_level(),
+    };
+}
@@ -1,5 +1,6 @@
+pub mod level;
+
 use std::collections::HashMap;
-use std::fmt::Debug;
 
 use bevy::{
     prelude::*,
@@ -7,9 +8,14 @@ use bevy::{
     window::PrimaryWindow
 };
 
-use crate::utils::game_settings::GameSettings;
+use crate::utils::{
+    colours::{GamePallete, get_colour},
+    game_settings::GameSettings,
+};
 use crate::AppState;
 use crate::game::SimulationState;
+use level::{Level, SelectedLevel, Terrain, load_level};
+pub use crate::flowy_core::grid::{CellId, CellCentre, GridSettings, Grid, Direction};
 
 
 pub struct GridPlugin;
@@ -20,301 +26,270 @@ impl Plugin for GridPlugin {
         app
             .init_resource::<GridIndex>()
             .init_resource::<GridSettings>()
-            .init_resource::<XYIndex>()
             .init_resource::<Grid>()
             .init_resource::<CursorGridIdx>()
-            .add_systems(OnEnter(AppState::Game), (create_grid_index, create_xy_index, create_grid, fill_all_cell_neighbours).chain())
+            .init_resource::<SelectedLevel>()
+            .init_resource::<Level>()
+            .init_resource::<GridBuild>()
+            .add_systems(OnEnter(AppState::Loading), (load_level, start_grid).chain())
+            .add_systems(Update, build_grid
+                .run_if(in_state(AppState::Loading)))
+            .add_systems(OnEnter(AppState::Game), spawn_terrain_tiles)
             .add_systems(Update, update_cursor_idx
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)))
-            .add_systems(OnExit(AppState::Game), cleanup_grid);
+            .add_systems(OnExit(AppState::Game), (cleanup_grid, cleanup_terrain_tiles));
     }
 }
 
-/// Used to create the uuid of each cell in the grid relating to that cells centre coords
+/// Used to find the id of each cell in the grid relating to that cells centre coords
 #[derive(Resource, Default, Debug)]
 pub struct GridIndex {
-    pub index: HashMap<CellCentre, Uuid>
+    pub index: HashMap<CellCentre, CellId>
 }
 
 
-#[derive(Debug, Eq, PartialEq, Hash, Default)]
-pub struct CellCentre {
-    pub x: i32,
-    pub y: i32,
+/// Marks the top half of both words of a uuid made from a cell, so other uuids aren't read back as cells
+const CELL_UUID_TAG: u64 = 0x6365_6c6c_0000_0000;
+const CELL_UUID_TAG_MASK: u64 = 0xffff_ffff_0000_0000;
+
+/// Cells map on to a uuid made from their column and row, for anything still keyed on uuids.
+/// The column and row keep all of their bits, so negative and large coordinates convert back unchanged.
+impl From<CellId> for Uuid {
+    fn from(cell_id: CellId) -> Self {
+        Uuid::from_u64_pair(CELL_UUID_TAG | cell_id.column as u32 as u64, CELL_UUID_TAG | cell_id.row as u32 as u64)
+    }
 }
 
-impl CellCentre {
-    fn new(x: i32, y: i32) -> Self {
-        Self {
-            x,
-            y,
+/// Only uuids made from a `CellId` convert back, anything else is handed back as the error
+impl TryFrom<Uuid> for CellId {
+    type Error = Uuid;
+
+    fn try_from(uuid: Uuid) -> Result<Self, Self::Error> {
+        let (column, row) = uuid.as_u64_pair();
+        if column & CELL_UUID_TAG_MASK != CELL_UUID_TAG || row & CELL_UUID_TAG_MASK != CELL_UUID_TAG {
+            return Err(uuid);
         }
+        Ok(CellId::new(column as u32 as i32, row as u32 as i32))
     }
 }
 
 
-#[derive(Resource, Default, Debug)]
-pub struct GridSettings {
-    pub cell_width: i32,
-    pub cell_height: i32,
-    pub grid_width: i32,
-    pub grid_height: i32,
-    pub number_of_horizontal_cells: i32,
-    pub number_of_vertical_cells: i32,
-    /// A vector of all cell centre points along the x axis
-    pub x_centers_vec: Vec<i32>,
-    /// A vector of all cell centre points along the y axis
-    pub y_centers_vec: Vec<i32>
-}
+impl Resource for GridSettings {}
 
+/// Where cells sit in world space, which is only needed for drawing the grid and picking cells with the mouse
+impl GridSettings {
+    /// Where the centre of a cell sits in world space. The grid is laid out centred on the world origin,
+    /// with rows counting down the screen and world y counting up.
+    pub fn cell_to_world(&self, centre: &CellCentre) -> Vec2 {
+        Vec2::new(
+            centre.x as f32 - self.grid_width as f32 / 2.0,
+            self.grid_height as f32 / 2.0 - centre.y as f32,
+        )
+    }
 
-/// Used as a lookup for x,y position and returns cell uuid
-#[derive(Resource, Default, Debug)]
-pub struct XYIndex {
-    pub index: HashMap<(i32, i32), Uuid>
+    /// The cell under a point in world space, the inverse of `cell_to_world`
+    pub fn world_to_cell(&self, world_position: Vec2) -> Option<CellId> {
+        let x = world_position.x + self.grid_width as f32 / 2.0;
+        let y = self.grid_height as f32 / 2.0 - world_position.y;
+        // floor so points just off the top or left edge don't round in to the first cell
+        self.cell_at(x.floor() as i32, y.floor() as i32)
+    }
 }
 
 
-/// Used to find cell information from cursor uuid
-#[derive(Resource, Default, Debug)]
-pub struct Grid {
-    pub cells: HashMap<Uuid, Cell>
-}
+impl Resource for Grid {}
 
 
-#[derive(Component, Default, Debug)]
-pub struct Cell {
-    pub id: Uuid,
-    /// The x,y position of the cell in the grid
-    pub centre: CellCentre,
-    /// The bounds of the cell (Left, Right, Top, Bottom)
-    pub bounds: (i32, i32, i32, i32),
-    /// Whether the cell is occupied by an entity
-    pub occupied: bool,
-    /// The uuid of the entity that is occupying this cell
-    pub occupied_by: Option<Uuid>,
-    /// The uuids of the neighbour cells
-    pub neighbours: CellNeighbours
-}
+/// The background sprite drawn under each cell showing its terrain
+#[derive(Component)]
+pub struct TerrainTile;
 
 
-#[derive(Debug, Default, Hash)]
-pub struct CellNeighbours {
-    pub left: Option<Uuid>,
-    pub right: Option<Uuid>,
-    pub top: Option<Uuid>,
-    pub bottom: Option<Uuid>
+/// Used to identify the position of our cursor relative to the grid, `None` when the cursor isn't over a cell.
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct CursorGridIdx {
+    pub index: Option<CellId>
 }
 
 
-/// Used to identify the position of our cursor relative to the grid.
-#[derive(Resource, Default)]
-pub struct CursorGridIdx {
-    pub index: Option<Uuid>
+/// How many cells are built each frame while loading, so a big map shows progress instead of freezing the window
+const CELLS_PER_FRAME: i32 = 4096;
+
+
+/// How far through building the grid for the level being loaded
+#[derive(Resource, Default, Debug)]
+pub struct GridBuild {
+    pub rows_built: i32,
+    pub rows: i32,
 }
 
+impl GridBuild {
+    /// Whether every row has been built and the cells joined to their neighbours
+    pub fn is_done(&self) -> bool {
+        self.rows_built >= self.rows
+    }
+}
 
-fn create_grid_index(
+
+/// Size the grid for the loaded level and clear out the previous one, ready for `build_grid` to fill in
+pub fn start_grid(
     game_settings: Res<GameSettings>,
+    level: Res<Level>,
     mut grid_index: ResMut<GridIndex>,
-    mut grid_settings: ResMut<GridSettings>
+    mut grid_settings: ResMut<GridSettings>,
+    mut grid: ResMut<Grid>,
+    mut grid_build: ResMut<GridBuild>,
 ) {
-    let cell_width = game_settings.grid.cell_width;
-    let cell_height = game_settings.grid.cell_height;
-    let grid_width = game_settings.window.resolution.width;
-    let grid_height = game_settings.window.resolution.height;
-
-    let number_of_horizontal_cells = grid_width / cell_width;
-    let number_of_vertical_cells = grid_height / cell_height;
-
-    for horiz_cell in 0..number_of_horizontal_cells {
-        for vert_cell in 0..number_of_vertical_cells {
-            let left = horiz_cell * cell_width;
-            let x = left + (cell_width / 2);
-            let top = vert_cell * cell_height;
-            let y = top + (cell_height / 2);
-
-            grid_index.index.insert(
-                CellCentre::new(x, y),
-                Uuid::new_v4()
-            );
-        }
-    }
-
     // update grid settings resource for faster compute in the next stages
-    grid_settings.cell_width = cell_width;
-    grid_settings.cell_height = cell_height;
-    grid_settings.grid_width = grid_width;
-    grid_settings.grid_height = grid_height;
-    grid_settings.number_of_horizontal_cells = number_of_horizontal_cells;
-    grid_settings.number_of_vertical_cells = number_of_vertical_cells;
-
-    let x_start_centre = grid_settings.cell_width / 2;
-    let x_centre_vec = (x_start_centre..grid_settings.grid_width)
-        .step_by(grid_settings.cell_width as usize)
-        .collect::<Vec<i32>>();
-    grid_settings.x_centers_vec = x_centre_vec.to_owned();
-
-    let y_start_centre = grid_settings.cell_height / 2;
-    let y_centre_vec = (y_start_centre..grid_settings.grid_height)
-        .step_by(grid_settings.cell_height as usize)
-        .collect::<Vec<i32>>();
-    grid_settings.y_centers_vec = y_centre_vec.to_owned();
+    *grid_settings = GridSettings::for_level(game_settings.grid.cell_width, game_settings.grid.cell_height, &level);
+
+    grid_index.index.clear();
+    *grid = Grid::new(game_settings.grid.chunk_size);
+    *grid_build = GridBuild {
+        rows_built: 0,
+        rows: level.rows,
+    };
 }
 
 
-fn create_xy_index(
-    grid_index: Res<GridIndex>,
+/// Build the next few rows of the grid, joining every cell to its neighbours once the last row is in
+pub fn build_grid(
     grid_settings: Res<GridSettings>,
-    mut xy_index: ResMut<XYIndex>
+    level: Res<Level>,
+    mut grid_index: ResMut<GridIndex>,
+    mut grid: ResMut<Grid>,
+    mut grid_build: ResMut<GridBuild>,
 ) {
-    for x in 0..grid_settings.grid_width {
-        for y in 0..grid_settings.grid_height {
-            let closest_x_centre = find_closest_value(&grid_settings.x_centers_vec, &x);
-            let closest_y_centre = find_closest_value(&grid_settings.y_centers_vec, &y);
-            xy_index.index.insert(
-                (x, y),
-                grid_index.index.get(&CellCentre::new(closest_x_centre, closest_y_centre)).unwrap().to_owned()
-            );
+    if grid_build.is_done() {
+        return;
+    }
+
+    let columns = grid_settings.number_of_horizontal_cells;
+    let rows_this_frame = (CELLS_PER_FRAME / columns.max(1)).max(1);
+    let first_row = grid_build.rows_built;
+    let last_row = (first_row + rows_this_frame).min(grid_build.rows);
+
+    for row in first_row..last_row {
+        grid.build_row(&grid_settings, &level, row);
+        for column in 0..columns {
+            let cell_id = CellId::new(column, row);
+            grid_index.index.insert(grid.cells[&cell_id].centre, cell_id);
         }
     }
+
+    if last_row == grid_build.rows {
+        grid.fill_neighbours();
+    }
+    grid_build.rows_built = last_row;
 }
 
 
-fn create_grid(
-    grid_index: Res<GridIndex>,
-    grid_settings: Res<GridSettings>,
-    mut grid: ResMut<Grid>,
-) {
-    for grid_cell in grid_index.index.iter() {
-        let centre_x = grid_cell.0.x;
-        let centre_y = grid_cell.0.y;
-        let left = centre_x - (grid_settings.cell_width / 2);
-        let right = centre_x + (grid_settings.cell_width / 2);
-        let top = centre_y - (grid_settings.cell_height / 2);
-        let bottom = centre_y + (grid_settings.cell_height / 2);
-
-        let cell_uuid = grid_cell.1.to_owned();
-       
-        grid.cells.insert(
-            cell_uuid,
-            Cell {
-                id: cell_uuid,
-                centre: CellCentre::new(centre_x, centre_y),
-                bounds: (left, right, top, bottom),
-                occupied: false,
-                occupied_by: None,
-                neighbours: CellNeighbours::default()
-            }
-        );
+fn terrain_colour(terrain: Terrain) -> Color {
+    match terrain {
+        Terrain::Grass => get_colour(GamePallete::TurquoiseGreen),
+        Terrain::Road => get_colour(GamePallete::Burlywood),
+        Terrain::Rock => get_colour(GamePallete::Wenge),
+        Terrain::River => get_colour(GamePallete::DesaturatedCyan),
+        Terrain::BuildingPlot => get_colour(GamePallete::DarkVanilla),
     }
 }
 
 
-fn fill_all_cell_neighbours(
+fn spawn_terrain_tiles(
+    mut commands: Commands,
+    grid: Res<Grid>,
     grid_settings: Res<GridSettings>,
-    grid_index: Res<GridIndex>,
-    mut grid: ResMut<Grid>,
 ) {
-    
-    for (_, cell) in grid.cells.iter_mut() {
-
-        let x_index = grid_settings.x_centers_vec.binary_search(&cell.centre.x).unwrap();  // Again im ok with killing the game here if cell indexes are borked, however this will never happen.
-        let y_index = grid_settings.y_centers_vec.binary_search(&cell.centre.y).unwrap();
-
-        if x_index == 0 {
-            // left side of map
-            cell.neighbours.left = None;
-        } else {
-            let left_neighbour_x_idx = x_index - 1;
-            let left_neighbour_y_idx = y_index;
-            cell.neighbours.left = get_cell_id_from_x_y_index(left_neighbour_x_idx, left_neighbour_y_idx, &grid_settings, &grid_index);
-        }
-
-        if x_index == grid_settings.x_centers_vec.len() - 1 {
-            // right side of map
-            cell.neighbours.right = None;
-        } else {
-            let right_neighbour_x_idx = x_index + 1;       
-            let right_neighbour_y_idx = y_index;
-            cell.neighbours.right = get_cell_id_from_x_y_index(right_neighbour_x_idx, right_neighbour_y_idx, &grid_settings, &grid_index);
-        }
-
-        if y_index == 0 {
-            // top row of map
-            cell.neighbours.top = None
-        } else {
-            let top_neighbour_x_idx = x_index;
-            let top_neighbour_y_idx = y_index - 1;
-            cell.neighbours.top = get_cell_id_from_x_y_index(top_neighbour_x_idx, top_neighbour_y_idx, &grid_settings, &grid_index);
-        }
-
-        if y_index == grid_settings.y_centers_vec.len() - 1 {
-            cell.neighbours.bottom = None;
-        } else {
-            let bottom_neighbour_x_idx = x_index;
-            let bottom_neighbour_y_idx = y_index + 1;
-            cell.neighbours.bottom = get_cell_id_from_x_y_index(bottom_neighbour_x_idx, bottom_neighbour_y_idx, &grid_settings, &grid_index);
-        }
+    // leave a thin gap between tiles so the grid lines show through
+    let tile_size = Vec2::new(grid_settings.cell_width as f32 - 2.0, grid_settings.cell_height as f32 - 2.0);
+
+    for cell in grid.cells.values() {
+        let world_position = grid_settings.cell_to_world(&cell.centre);
+
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(tile_size),
+                    color: terrain_colour(cell.terrain),
+                    ..default()
+                },
+                // sits behind all equipment
+                transform: Transform::from_xyz(world_position.x, world_position.y, -1.0),
+                ..default()
+            },
+            TerrainTile,
+        ));
     }
 }
 
 
+/// Project the cursor through the camera into world space to find the cell under it, wherever the camera has been moved to
 pub fn update_cursor_idx(
     grid_settings: Res<GridSettings>,
-    xy_index: Res<XYIndex>,
     mut cursor_idx: ResMut<CursorGridIdx>,
-    q_window: Query<&Window, With<PrimaryWindow>>
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform)>,
 ) {
     // There is only one primary window, so we can get it from the query:
     let window = q_window.single();
+    let (camera, camera_transform) = q_camera.single();
 
-    if let Some(world_position) = window.cursor_position()
-        .map(|cursor| (cursor.x as i32, cursor.y as i32)) {
-            let cell_centre = (
-                find_closest_value(&grid_settings.x_centers_vec, &world_position.0),
-                find_closest_value(&grid_settings.y_centers_vec, &world_position.1)
-            );
-
-            cursor_idx.index = xy_index.index
-                .get(&cell_centre)
-                .map(|idx| idx.to_owned());
-        }
+    let index = window.cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor))
+        .and_then(|world_position| grid_settings.world_to_cell(world_position));
+    // only flagged as changed when the cursor moves to another cell
+    cursor_idx.set_if_neq(CursorGridIdx { index });
 }
 
 
-/// Used to find the closest value in a vector to a target value. This uses binary search for O(log n) time complexity
-/// which will help with large vectors.
-pub fn find_closest_value(vector: &Vec<i32>, target: &i32) -> i32 {
-    let idx = match vector.binary_search_by(|&x| x.cmp(target)) {
-        Ok(idx) => idx, // Exact match
-        Err(idx) => {
-            if idx > 0 && (idx == vector.len() || (vector[idx] - target).abs() > (vector[idx - 1] - target).abs()) {
-                idx - 1
-            } else {
-                idx
-            }
-        }
-    };
-
-    vector[idx].to_owned()
+fn cleanup_grid(
+    mut grid_index: ResMut<GridIndex>,
+    mut grid: ResMut<Grid>,
+    mut cursor_idx: ResMut<CursorGridIdx>,
+) {
+    // the next level may have a different layout so the lookups must be rebuilt from scratch, and the cell last under
+    // the cursor may not be in it
+    grid_index.index.clear();
+    grid.cells.clear();
+    *cursor_idx = CursorGridIdx::default();
 }
 
 
-pub fn get_cell_id_from_x_y_index(x_index: usize, y_index: usize, grid_settings: &Res<GridSettings>, grid_index: &Res<GridIndex>) -> Option<Uuid> {
-    let x_centre = grid_settings.x_centers_vec[x_index];
-    let y_centre = grid_settings.y_centers_vec[y_index];
-    grid_index.index
-        .get(&CellCentre::new(x_centre, y_centre))
-        .map(|idx| idx.to_owned())
-}
-
-fn cleanup_grid(
+fn cleanup_terrain_tiles(
     mut commands: Commands,
-    q_cells: Query<(Entity, &Cell)>,
+    q_terrain_tiles: Query<Entity, With<TerrainTile>>,
 ) {
-    for (entity, _) in q_cells.iter() {
+    for entity in q_terrain_tiles.iter() {
         commands.entity(entity).despawn_recursive();
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_ids_round_trip_through_a_uuid() {
+        for cell_id in [
+            CellId::new(0, 0),
+            CellId::new(-1, -7),
+            CellId::new(i32::MIN, i32::MAX),
+            CellId::new(i32::MAX, i32::MIN),
+        ] {
+            assert_eq!(CellId::try_from(Uuid::from(cell_id)), Ok(cell_id));
+        }
+    }
+
+    #[test]
+    fn foreign_uuids_are_not_cells() {
+        for uuid in [
+            Uuid::nil(),
+            Uuid::from_u64_pair(3, 4),
+            Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8),
+        ] {
+            assert_eq!(CellId::try_from(uuid), Err(uuid));
+        }
+    }
+}
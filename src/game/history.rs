@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::game::grid::{Grid, CellId};
+use crate::game::equipment::{Equipment, ToBeDespawned, flag_equipment, flag_to_spawn, flag_to_despawn, kind::{EquipmentKind, Rotation}};
+use crate::AppState;
+use crate::game::SimulationState;
+
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<History>()
+            .add_systems(Update, undo_redo
+                .before(flag_equipment)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)))
+            .add_systems(OnExit(AppState::Game), cleanup_history);
+    }
+}
+
+
+/// A piece of equipment as it was laid in a cell, along with what it cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquipmentRecord {
+    pub kind: EquipmentKind,
+    pub rotation: Rotation,
+    pub cost: i32,
+}
+
+/// A single change the player made to the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    Spawned(CellId, EquipmentRecord),
+    Despawned(CellId, EquipmentRecord),
+}
+
+
+/// What a cell held before and after a stroke
+#[derive(Debug, Clone)]
+struct CellChange {
+    cell_idx: CellId,
+    before: Option<EquipmentRecord>,
+    after: Option<EquipmentRecord>,
+}
+
+/// Every cell changed while the cursor was in a single placing or deleting run, which is undone as one step
+#[derive(Debug, Default, Clone)]
+struct Stroke {
+    changes: Vec<CellChange>,
+}
+
+impl Stroke {
+    fn record(&mut self, edit: Edit) {
+        let (cell_idx, before, after) = match edit {
+            Edit::Spawned(cell_idx, record) => (cell_idx, None, Some(record)),
+            Edit::Despawned(cell_idx, record) => (cell_idx, Some(record), None),
+        };
+        // only the first and last state of a cell matter, e.g. a fitting upgraded several times in one stroke
+        match self.changes.iter_mut().find(|change| change.cell_idx == cell_idx) {
+            Some(change) => change.after = after,
+            None => self.changes.push(CellChange { cell_idx, before, after }),
+        }
+    }
+}
+
+
+/// The strokes that can be undone and redone
+#[derive(Resource, Default, Debug)]
+pub struct History {
+    current: Stroke,
+    undo_stack: Vec<Stroke>,
+    redo_stack: Vec<Stroke>,
+}
+
+impl History {
+    pub fn record(&mut self, edit: Edit) {
+        self.current.record(edit);
+    }
+
+    /// Close off the stroke in progress so the next edit starts a new undo step
+    pub fn end_stroke(&mut self) {
+        let mut stroke = std::mem::take(&mut self.current);
+        stroke.changes.retain(|change| change.before != change.after);
+        if !stroke.changes.is_empty() {
+            self.undo_stack.push(stroke);
+            // a new edit branches off the history so there is nothing left to redo
+            self.redo_stack.clear();
+        }
+    }
+
+    /// Step back a stroke, returning what each cell it touched should go back to holding
+    pub fn undo(&mut self) -> Vec<(CellId, Option<EquipmentRecord>)> {
+        self.end_stroke();
+        let Some(stroke) = self.undo_stack.pop() else {
+            return Vec::new();
+        };
+        let targets = stroke.changes
+            .iter()
+            .rev()
+            .map(|change| (change.cell_idx, change.before))
+            .collect();
+        self.redo_stack.push(stroke);
+        targets
+    }
+
+    /// Step forward a stroke, returning what each cell it touched should hold again
+    pub fn redo(&mut self) -> Vec<(CellId, Option<EquipmentRecord>)> {
+        self.end_stroke();
+        let Some(stroke) = self.redo_stack.pop() else {
+            return Vec::new();
+        };
+        let targets = stroke.changes
+            .iter()
+            .map(|change| (change.cell_idx, change.after))
+            .collect();
+        self.undo_stack.push(stroke);
+        targets
+    }
+}
+
+
+/// Undo the last stroke with Ctrl+Z and redo it with Ctrl+Y.
+/// Equipment is put back with the cost it was originally charged, so the budget ends up exactly where it was.
+pub fn undo_redo(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut history: ResMut<History>,
+    mut grid: ResMut<Grid>,
+    q_equipment: Query<(Entity, &Equipment), Without<ToBeDespawned>>,
+) {
+    if !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let targets = if keyboard_input.just_pressed(KeyCode::Z) {
+        history.undo()
+    } else if keyboard_input.just_pressed(KeyCode::Y) {
+        history.redo()
+    } else {
+        return;
+    };
+
+    for (cell_idx, target) in targets {
+        for (entity, equipment) in q_equipment.iter() {
+            if equipment.cell_idx == cell_idx {
+                flag_to_despawn(&mut commands, entity);
+            }
+        }
+
+        match target {
+            Some(record) => flag_to_spawn(&mut commands, &mut grid, cell_idx, record.kind, record.rotation, record.cost),
+            None => {
+                if let Some(cell) = grid.cells.get_mut(&cell_idx) {
+                    cell.occupied = false;
+                }
+            },
+        }
+    }
+}
+
+
+fn cleanup_history(
+    mut history: ResMut<History>,
+) {
+    *history = History::default();
+}
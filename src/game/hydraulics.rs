@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::utils::game_settings::GameSettings;
+use crate::game::grid::{Grid, CellId};
+use crate::game::equipment::{Equipment, SpawnedEquipment, kind::EquipmentKind, fixture::{Fixture, FixtureMeta}};
+use crate::game::flow::{FlowNetwork, collect_equipment_ports, update_flow_network};
+use crate::AppState;
+use crate::game::SimulationState;
+pub use crate::flowy_core::hydraulics::{HydraulicState, solve_hydraulics};
+
+
+pub struct HydraulicsPlugin;
+
+impl Plugin for HydraulicsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HydraulicState>()
+            .add_systems(Update, update_hydraulics
+                .after(update_flow_network)
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running))
+                .run_if(resource_changed::<FlowNetwork>()))
+            .add_systems(OnExit(AppState::Game), cleanup_hydraulics);
+    }
+}
+
+
+impl Resource for HydraulicState {}
+
+
+/// A one line summary of the hydraulic state, printed when it changes
+fn hydraulics_summary(hydraulic_state: &HydraulicState) -> String {
+    format!(
+        "Hydraulics updated: {}/{} consumers supplied, {} stations overloaded",
+        hydraulic_state.supplied_consumers.len(),
+        hydraulic_state.supplied_consumers.len() + hydraulic_state.starved_consumers.len(),
+        hydraulic_state.overloaded_stations.len()
+    )
+}
+
+
+pub fn update_hydraulics(
+    game_settings: Res<GameSettings>,
+    grid: Res<Grid>,
+    mut hydraulic_state: ResMut<HydraulicState>,
+    q_equipment: Query<(&Equipment, &EquipmentKind), With<SpawnedEquipment>>,
+    q_fixtures: Query<&Fixture>,
+) {
+    let equipment_ports = collect_equipment_ports(q_equipment.iter());
+    let equipment = q_equipment
+        .iter()
+        .map(|(equipment, kind)| (equipment.cell_idx, *kind))
+        .collect::<HashMap<CellId, EquipmentKind>>();
+
+    let fixtures = q_fixtures
+        .iter()
+        .map(|fixture| (fixture.cell_idx, fixture.meta))
+        .collect::<HashMap<CellId, FixtureMeta>>();
+
+    let previous_summary = hydraulics_summary(&hydraulic_state);
+    *hydraulic_state = solve_hydraulics(&grid, &equipment_ports, &equipment, &fixtures, &game_settings);
+
+    // the flow network changes with every piece laid, the supply far less often
+    let summary = hydraulics_summary(&hydraulic_state);
+    if summary != previous_summary {
+        println!("{}", summary);
+    }
+}
+
+
+fn cleanup_hydraulics(
+    mut hydraulic_state: ResMut<HydraulicState>,
+) {
+    *hydraulic_state = HydraulicState::default();
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::utils::{
+    colours::{GamePallete, get_colour},
+    ui::{HudCorner, hud_style},
+};
+use crate::AppState;
+pub use crate::flowy_core::ledger::{Ledger, CostCategory};
+
+
+pub struct LedgerPlugin;
+
+impl Plugin for LedgerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Ledger>()
+            .add_systems(OnEnter(AppState::Game), (reset_ledger, spawn_ledger_panel))
+            .add_systems(Update, (toggle_ledger_panel, render_ledger_panel)
+                .run_if(in_state(AppState::Game)))
+            .add_systems(OnExit(AppState::Game), cleanup_ledger_panel);
+    }
+}
+
+
+impl Resource for Ledger {}
+
+
+/// Identifies the panel of spending totals, which is hidden until it is toggled on
+#[derive(Component)]
+pub struct LedgerPanel;
+
+
+fn reset_ledger(
+    mut ledger: ResMut<Ledger>,
+) {
+    *ledger = Ledger::default();
+}
+
+
+/// The panel has a label and a value section for each category followed by the overall total
+fn spawn_ledger_panel(
+    mut commands: Commands,
+) {
+    let font_handle: Handle<Font> = Default::default();
+    let section = |value: String, colour: GamePallete| TextSection::new(
+        value,
+        TextStyle {
+            font: font_handle.clone(),
+            font_size: 30.0,
+            color: get_colour(colour),
+        },
+    );
+
+    let mut sections = Vec::new();
+    for category in CostCategory::ALL {
+        sections.push(section(format!("{}:  ", category.label()), GamePallete::Feldgrau));
+        sections.push(section(String::from("0\n"), GamePallete::JapaneseIndigo));
+    }
+    sections.push(section(String::from("Total:  "), GamePallete::Feldgrau));
+    sections.push(section(String::from("0"), GamePallete::JapaneseIndigo));
+
+    commands.spawn((
+        TextBundle::from_sections(sections)
+            .with_style(hud_style(HudCorner::BottomRight)),
+        LedgerPanel,
+    )).insert(Visibility::Hidden);
+}
+
+
+/// Show or hide the spending totals with L
+fn toggle_ledger_panel(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut q_panel: Query<&mut Visibility, With<LedgerPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::L) {
+        return;
+    }
+
+    for mut visibility in q_panel.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+
+fn render_ledger_panel(
+    ledger: Res<Ledger>,
+    mut q_panel: Query<&mut Text, With<LedgerPanel>>,
+) {
+    if !ledger.is_changed() {
+        return;
+    }
+
+    for mut text in q_panel.iter_mut() {
+        for (i, category) in CostCategory::ALL.iter().enumerate() {
+            text.sections[i * 2 + 1].value = format!("{}\n", ledger.total(*category));
+        }
+        let last = text.sections.len() - 1;
+        text.sections[last].value = ledger.spent().to_string();
+    }
+}
+
+
+fn cleanup_ledger_panel(
+    mut commands: Commands,
+    q_panel: Query<Entity, With<LedgerPanel>>,
+) {
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
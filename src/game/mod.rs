@@ -1,18 +1,39 @@
 mod grid;
 mod cursor;
+mod camera;
 mod equipment;
 mod timer;
+mod flow;
+mod hydraulics;
+mod history;
+mod save;
+mod ledger;
+pub mod evaluation;
 
 
 use bevy::prelude::*;
 
 use grid::GridPlugin;
 use cursor::CursorPlugin;
+use camera::CameraPlugin;
 use equipment::EquipmentPlugin;
-use timer::{GameTimer, tick_game_timer, handle_timer_text, render_timer_text, cleanup_timer};
+use flow::FlowPlugin;
+use hydraulics::HydraulicsPlugin;
+use history::HistoryPlugin;
+use save::SavePlugin;
+use ledger::LedgerPlugin;
+use hydraulics::update_hydraulics;
+use timer::{GameTimer, tick_game_timer, reset_game_timer, handle_timer_text, render_timer_text, cleanup_timer};
+use evaluation::{LevelOutcome, LevelResult, evaluate_level, end_level};
 use crate::utils::game_settings::GameSettings;
 use crate::AppState;
 
+pub use grid::{CursorGridIdx, GridBuild, build_grid, level::{Level, SelectedLevel}};
+pub use save::{SaveGame, SaveFile, PendingSave};
+pub use cursor::CursorModeText;
+pub use ledger::{Ledger, LedgerPanel};
+pub use equipment::{BudgetText, Ghost, SpawnedEquipment};
+
 
 #[derive(States, Default, Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum SimulationState {
@@ -23,7 +44,9 @@ pub enum SimulationState {
 
 
 #[derive(Event)]
-pub struct GameOver;
+pub struct GameOver {
+    pub outcome: LevelOutcome
+}
 
 
 pub struct GamePlugin;
@@ -34,15 +57,25 @@ impl Plugin for GamePlugin {
             .add_state::<SimulationState>()
             .init_resource::<GameSettings>()
             .init_resource::<GameTimer>()
+            .init_resource::<LevelResult>()
             .add_event::<GameOver>()
             .add_plugins(GridPlugin)
             .add_plugins(CursorPlugin)
+            .add_plugins(CameraPlugin)
             .add_plugins(EquipmentPlugin)
-            .add_systems(OnEnter(AppState::Game), handle_timer_text)
+            .add_plugins(FlowPlugin)
+            .add_plugins(HydraulicsPlugin)
+            .add_plugins(HistoryPlugin)
+            .add_plugins(SavePlugin)
+            .add_plugins(LedgerPlugin)
+            .add_systems(OnEnter(AppState::Game), (reset_game_timer, handle_timer_text).chain())
             .add_systems(Update, toggle_simulation.run_if(in_state(AppState::Game)))
             .add_systems(Update, (tick_game_timer, render_timer_text).chain()
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)))
+            .add_systems(Update, (evaluate_level.after(update_hydraulics).after(tick_game_timer), end_level).chain()
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)))
             .add_systems(OnExit(AppState::Game), cleanup_timer);
     }
 }
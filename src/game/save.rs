@@ -0,0 +1,265 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::grid::{Grid, CellId, level::Level};
+use crate::game::equipment::{Budget, BudgetedEquipment, Equipment, ToBeSpawned, kind::{EquipmentKind, Rotation}};
+use crate::game::history::History;
+use crate::game::timer::GameTimer;
+use crate::game::ledger::Ledger;
+use crate::flowy_core::ledger::{LedgerEntry, Transaction};
+use crate::utils::game_settings::{GameSettings, asset_root};
+use crate::AppState;
+
+
+/// The folder inside the platform data dir that the quicksave is kept in
+const SAVE_DIR_NAME: &str = "flowy";
+const SAVE_FILE_NAME: &str = "quicksave.toml";
+
+
+/// The file the quicksave is written to and read from
+#[derive(Resource, Debug, Clone)]
+pub struct SaveFile(pub PathBuf);
+
+impl Default for SaveFile {
+    /// In the platform data dir, or next to the game's assets on a platform without one
+    fn default() -> Self {
+        let dir = dirs::data_dir().unwrap_or_else(asset_root);
+        Self(dir.join(SAVE_DIR_NAME).join(SAVE_FILE_NAME))
+    }
+}
+
+impl SaveFile {
+    /// Whether there is a quicksave to continue from
+    pub fn exists(&self) -> bool {
+        self.0.exists()
+    }
+}
+
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<PendingSave>()
+            .init_resource::<SaveFile>()
+            .add_systems(Update, (save_game, load_game)
+                .run_if(in_state(AppState::Game)))
+            .add_systems(Update, (restore_equipment, restore_progress)
+                .chain()
+                .run_if(in_state(AppState::Game))
+                .run_if(pending_save_exists));
+    }
+}
+
+
+/// A half built network, with cells stored by their column and row
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SaveGame {
+    /// The key of the level in the `[levels]` table
+    pub level_id: Option<String>,
+    pub budget: i32,
+    /// Seconds left on the level timer
+    pub time_left: f32,
+    /// Every piece of equipment the player has laid, the cells they sit in are the occupied ones
+    pub equipment: Vec<SavedEquipment>,
+}
+
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedEquipment {
+    pub column: i32,
+    pub row: i32,
+    pub kind: EquipmentKind,
+    pub rotation: Rotation,
+    pub cost: i32,
+}
+
+
+/// A save waiting to be applied once the level it belongs to has been set up
+#[derive(Resource, Default, Debug)]
+pub struct PendingSave(pub Option<SaveGame>);
+
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Serialize(toml::ser::Error),
+    Deserialize(toml::de::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "could not access save file: {}", err),
+            SaveError::Serialize(err) => write!(f, "could not write save: {}", err),
+            SaveError::Deserialize(err) => write!(f, "could not parse save file: {}", err),
+        }
+    }
+}
+
+
+impl SaveGame {
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(path).map_err(SaveError::Io)?;
+        toml::from_str(&contents).map_err(SaveError::Deserialize)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        let contents = toml::to_string(self).map_err(SaveError::Serialize)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(SaveError::Io)?;
+        }
+        fs::write(path, contents).map_err(SaveError::Io)
+    }
+}
+
+
+fn pending_save_exists(
+    pending_save: Res<PendingSave>,
+) -> bool {
+    pending_save.0.is_some()
+}
+
+
+/// Write the current network to the quicksave with F5
+fn save_game(
+    keyboard_input: Res<Input<KeyCode>>,
+    save_file: Res<SaveFile>,
+    level: Res<Level>,
+    budget: Res<Budget>,
+    timer: Res<GameTimer>,
+    q_equipment: Query<(&Equipment, &EquipmentKind), With<BudgetedEquipment>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    // only budgeted equipment is saved so the saved budget always matches what is on the grid
+    let equipment = q_equipment
+        .iter()
+        .filter_map(|(equipment, kind)| {
+            Some(SavedEquipment {
+                column: equipment.cell_idx.column,
+                row: equipment.cell_idx.row,
+                kind: *kind,
+                rotation: equipment.rotation,
+                cost: equipment.cost?,
+            })
+        })
+        .collect();
+
+    let save_game = SaveGame {
+        level_id: level.id.clone(),
+        budget: budget.0,
+        time_left: timer.timer.remaining_secs(),
+        equipment,
+    };
+    match save_game.write(&save_file.0) {
+        Ok(()) => println!("Game saved to {}", save_file.0.display()),
+        Err(err) => println!("Failed to save game: {}", err),
+    }
+}
+
+
+/// Reload the quicksave with F9, as long as it belongs to the level being played
+fn load_game(
+    keyboard_input: Res<Input<KeyCode>>,
+    save_file: Res<SaveFile>,
+    level: Res<Level>,
+    mut pending_save: ResMut<PendingSave>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    match SaveGame::read(&save_file.0) {
+        Ok(save_game) if save_game.level_id == level.id => pending_save.0 = Some(save_game),
+        Ok(_) => println!("Quicksave is for a different level, continue it from the main menu"),
+        Err(err) => println!("Failed to load game: {}", err),
+    }
+}
+
+
+/// Swap everything the player has laid for the equipment in the pending save.
+/// The ledger starts again with a charge for each restored piece, as the save doesn't keep how it was built up.
+#[allow(clippy::too_many_arguments)]
+fn restore_equipment(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    pending_save: Res<PendingSave>,
+    level: Res<Level>,
+    timer: Res<GameTimer>,
+    mut grid: ResMut<Grid>,
+    mut ledger: ResMut<Ledger>,
+    q_equipment: Query<Entity, With<Equipment>>,
+) {
+    // the run condition guarantees there is a save
+    let save_game = pending_save.0.as_ref().unwrap();
+    if save_game.level_id != level.id {
+        println!("Save is for a different level, ignoring it");
+        return;
+    }
+
+    for entity in q_equipment.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for cell in grid.cells.values_mut() {
+        cell.occupied = cell.feature.is_some();
+    }
+    *ledger = Ledger::default();
+    let saved_at = timer.timer.duration().saturating_sub(Duration::from_secs_f32(save_game.time_left.max(0.0)));
+
+    for saved in save_game.equipment.iter() {
+        let cell_idx = CellId::new(saved.column, saved.row);
+        let Some(cell) = grid.cells.get_mut(&cell_idx) else {
+            println!("Saved {} at column {}, row {} is off the grid", saved.kind.label(), saved.column, saved.row);
+            continue;
+        };
+        cell.occupied = true;
+        ledger.record(LedgerEntry::new(Transaction::Charge, saved.kind, cell_idx, cell.terrain, saved.cost, saved_at, &game_settings));
+
+        // the saved budget already has this equipment paid for
+        commands.spawn((
+            Equipment {
+                cell_idx,
+                rotation: saved.rotation,
+                cost: Some(saved.cost),
+            },
+            saved.kind,
+            ToBeSpawned,
+            BudgetedEquipment,
+        ));
+    }
+}
+
+
+fn restore_progress(
+    save_file: Res<SaveFile>,
+    level: Res<Level>,
+    mut pending_save: ResMut<PendingSave>,
+    mut budget: ResMut<Budget>,
+    mut timer: ResMut<GameTimer>,
+    mut history: ResMut<History>,
+) {
+    let Some(save_game) = pending_save.0.take() else {
+        return;
+    };
+    if save_game.level_id != level.id {
+        return;
+    }
+
+    budget.0 = save_game.budget;
+    let elapsed = timer.timer.duration().saturating_sub(Duration::from_secs_f32(save_game.time_left.max(0.0)));
+    timer.timer.set_elapsed(elapsed);
+
+    // nothing from before the load can be undone on to the restored network
+    *history = History::default();
+
+    println!("Game loaded from {}", save_file.0.display());
+}
@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 
-use crate::game::GameOver;
-use crate::utils::colours::{GamePallete, get_colour};
+use crate::game::{GameOver, evaluation::LevelOutcome};
+use crate::flowy_core::timer::LevelTimer;
+use crate::utils::{
+    colours::{GamePallete, get_colour},
+    ui::{HudCorner, hud_style},
+};
 
 #[derive(Resource)]
 pub struct GameTimer {
-    pub timer: Timer
+    pub timer: LevelTimer
 }
 
 #[derive(Component)]
@@ -16,7 +20,7 @@ impl Default for GameTimer {
     fn default() -> Self {
         // TODO: update depending on level
         Self {
-            timer: Timer::from_seconds(30.0, TimerMode::Once)
+            timer: LevelTimer::from_seconds(30.0)
         }
     }
 }
@@ -27,12 +31,17 @@ pub fn tick_game_timer(
     mut timer: ResMut<GameTimer>,
     mut game_over_writer: ResMut<Events<GameOver>>,
 ) {
-    timer.timer.tick(time.delta());
-    if timer.timer.finished() {
-        game_over_writer.send(GameOver);
+    if timer.timer.tick(time.delta()) {
+        game_over_writer.send(GameOver { outcome: LevelOutcome::OutOfTime });
     }
 }
 
+pub fn reset_game_timer(
+    mut timer: ResMut<GameTimer>,
+) {
+    *timer = GameTimer::default();
+}
+
 pub fn handle_timer_text(
     mut commands: Commands,
     timer: Res<GameTimer>,
@@ -55,7 +64,7 @@ pub fn handle_timer_text(
                 font_size: 60.0,
                 color: get_colour(GamePallete::JapaneseIndigo),
             },
-        )]),
+        )]).with_style(hud_style(HudCorner::BottomLeft)),
         GameTimerText
     ));
 }
@@ -1,10 +1,129 @@
 use bevy::prelude::*;
 
+use crate::utils::{
+    colours::GamePallete,
+    game_settings::GameSettings,
+    ui::{spawn_button, spawn_label, menu_root, highlight_buttons},
+};
+use crate::game::{SelectedLevel, evaluation::LevelResult};
+use crate::flowy_core::ledger::CostCategory;
+use crate::AppState;
+
 
 pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app;
+        app
+            .add_systems(OnEnter(AppState::GameOver), spawn_results_screen)
+            .add_systems(Update, (highlight_buttons, handle_results_buttons)
+                .run_if(in_state(AppState::GameOver)))
+            .add_systems(OnExit(AppState::GameOver), cleanup_results_screen);
+    }
+}
+
+
+/// Identifies the root of the results screen
+#[derive(Component)]
+pub struct ResultsScreen;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultsButton {
+    Retry,
+    NextLevel,
+    MainMenu,
+}
+
+
+/// The level after the selected one in the `[levels]` table, if there is one
+fn next_level_id(game_settings: &GameSettings, selected_level: &SelectedLevel) -> Option<String> {
+    let current = selected_level.id.as_ref()?;
+    game_settings.levels
+        .keys()
+        .skip_while(|id| *id != current)
+        .nth(1)
+        .cloned()
+}
+
+
+fn spawn_results_screen(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    selected_level: Res<SelectedLevel>,
+    level_result: Res<LevelResult>,
+) {
+    let Some(outcome) = level_result.outcome else {
+        return;
+    };
+    let has_next_level = outcome.is_win() && next_level_id(&game_settings, &selected_level).is_some();
+
+    commands
+        .spawn((menu_root(), ResultsScreen))
+        .with_children(|parent| {
+            spawn_label(parent, outcome.title(), 60.0, GamePallete::JapaneseIndigo);
+            spawn_label(parent, level_result.level_name.clone(), 30.0, GamePallete::Feldgrau);
+            spawn_label(parent, outcome.description(), 20.0, GamePallete::Feldgrau);
+            spawn_label(parent, format!("Money spent:  {}", level_result.money_spent), 30.0, GamePallete::JapaneseIndigo);
+            spawn_label(parent, format!("Time left:  {}", level_result.time_left as i32), 30.0, GamePallete::JapaneseIndigo);
+            if level_result.overdraft > 0 {
+                let penalty = level_result.overdraft * game_settings.gameplay.overdraft_penalty;
+                spawn_label(parent, format!("Overdrawn by {}:  -{} points", level_result.overdraft, penalty), 20.0, GamePallete::Feldgrau);
+            }
+            spawn_label(parent, format!("Score:  {}", level_result.score), 30.0, GamePallete::JapaneseIndigo);
+            spawn_invoice(parent, &level_result);
+
+            spawn_button(parent, "Retry", ResultsButton::Retry);
+            if has_next_level {
+                spawn_button(parent, "Next Level", ResultsButton::NextLevel);
+            }
+            spawn_button(parent, "Main Menu", ResultsButton::MainMenu);
+        });
+}
+
+
+/// An itemised bill for what was left on the grid, followed by the totals for each category
+fn spawn_invoice(parent: &mut ChildBuilder, level_result: &LevelResult) {
+    for line in level_result.ledger.invoice() {
+        let text = format!("{} x {} on {}:  {}", line.quantity, line.kind.label(), line.terrain.label(), line.total);
+        spawn_label(parent, text, 16.0, GamePallete::Feldgrau);
+    }
+
+    let totals = CostCategory::ALL
+        .iter()
+        .map(|category| format!("{}:  {}", category.label(), level_result.ledger.total(*category)))
+        .collect::<Vec<_>>()
+        .join("    ");
+    spawn_label(parent, totals, 20.0, GamePallete::JapaneseIndigo);
+}
+
+
+fn handle_results_buttons(
+    game_settings: Res<GameSettings>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut app_state: ResMut<NextState<AppState>>,
+    q_buttons: Query<(&Interaction, &ResultsButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ResultsButton::Retry => app_state.set(AppState::Loading),
+            ResultsButton::NextLevel => {
+                selected_level.id = next_level_id(&game_settings, &selected_level);
+                app_state.set(AppState::Loading);
+            },
+            ResultsButton::MainMenu => app_state.set(AppState::MainMenu),
+        }
+    }
+}
+
+
+fn cleanup_results_screen(
+    mut commands: Commands,
+    q_results_screen: Query<Entity, With<ResultsScreen>>,
+) {
+    for entity in q_results_screen.iter() {
+        commands.entity(entity).despawn_recursive();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,28 @@
+//! The game as a library, so the binary and the tests build the same `App`.
+//!
+//! The rules of the game live in [`flowy_core`], which has no Bevy in it and can be run headless.
+//! Everything else is the Bevy layer on top that draws the game and feeds it input.
+
+pub mod flowy_core;
+pub mod main_menu;
+pub mod loading;
+pub mod game;
+pub mod game_over;
+pub mod utils;
+
+use bevy::prelude::*;
+
+pub const GAME_TITLE: &str = env!("CARGO_PKG_NAME");
+pub const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub static SETTINGS_STR: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/game_settings.toml"));
+
+
+#[derive(States, Default, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    /// Loading the textures and building the grid for a level before it starts
+    Loading,
+    Game,
+    GameOver,
+}
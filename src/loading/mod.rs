@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+use crate::utils::{
+    assets::AssetHandles,
+    colours::{GamePallete, get_colour},
+    game_settings::GameSettings,
+    ui::{spawn_label, menu_root},
+};
+use crate::game::{GridBuild, build_grid};
+use crate::AppState;
+
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AssetHandles>()
+            .add_systems(OnEnter(AppState::Loading), (load_textures, spawn_loading_screen))
+            .add_systems(Update, (update_progress_bar, finish_loading)
+                .after(build_grid)
+                .run_if(in_state(AppState::Loading)))
+            .add_systems(OnExit(AppState::Loading), cleanup_loading_screen);
+    }
+}
+
+
+/// Identifies the root of the loading screen
+#[derive(Component)]
+pub struct LoadingScreen;
+
+/// Identifies the filled part of the progress bar
+#[derive(Component)]
+pub struct ProgressBar;
+
+
+/// How much of one part of loading is done, a part with nothing to do counts as finished
+fn fraction(done: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 1.0;
+    }
+    done as f32 / total as f32
+}
+
+
+/// How far through loading the level is, with the textures and the grid counting for half each
+fn loading_progress(asset_server: &AssetServer, asset_handles: &AssetHandles, grid_build: &GridBuild) -> f32 {
+    let textures = fraction(asset_handles.settled(asset_server), asset_handles.0.len());
+    let grid = fraction(grid_build.rows_built as usize, grid_build.rows as usize);
+    (textures + grid) / 2.0
+}
+
+
+/// Start loading the texture of every asset in the settings
+fn load_textures(
+    game_settings: Res<GameSettings>,
+    asset_server: Res<AssetServer>,
+    mut asset_handles: ResMut<AssetHandles>,
+) {
+    // textures already loaded for an earlier level are handed straight back by the asset server
+    *asset_handles = AssetHandles::load(&game_settings.assets, &asset_server);
+}
+
+
+fn spawn_loading_screen(
+    mut commands: Commands,
+) {
+    commands
+        .spawn((menu_root(), LoadingScreen))
+        .with_children(|parent| {
+            spawn_label(parent, "Loading", 60.0, GamePallete::JapaneseIndigo);
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(300.0),
+                        height: Val::Px(24.0),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..default()
+                    },
+                    background_color: get_colour(GamePallete::Feldgrau).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: get_colour(GamePallete::JapaneseIndigo).into(),
+                            ..default()
+                        },
+                        ProgressBar,
+                    ));
+                });
+        });
+}
+
+
+fn update_progress_bar(
+    asset_server: Res<AssetServer>,
+    asset_handles: Res<AssetHandles>,
+    grid_build: Res<GridBuild>,
+    mut q_progress_bar: Query<&mut Style, With<ProgressBar>>,
+) {
+    let progress = loading_progress(&asset_server, &asset_handles, &grid_build);
+
+    for mut style in q_progress_bar.iter_mut() {
+        style.width = Val::Percent(progress * 100.0);
+    }
+}
+
+
+/// Start the level once every texture has settled and the grid is built
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    asset_handles: Res<AssetHandles>,
+    grid_build: Res<GridBuild>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    if asset_handles.settled(&asset_server) == asset_handles.0.len() && grid_build.is_done() {
+        app_state.set(AppState::Game);
+    }
+}
+
+
+fn cleanup_loading_screen(
+    mut commands: Commands,
+    q_loading_screen: Query<Entity, With<LoadingScreen>>,
+) {
+    for entity in q_loading_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,63 +1,61 @@
-mod main_menu;
-mod game;
-mod game_over;
-mod utils;
-
 use bevy::{
     prelude::*,
     core_pipeline::clear_color::ClearColorConfig,
     app::AppExit,
 };
 
-use main_menu::MainMenuPlugin;
-use utils::colours::{GamePallete, get_colour};
-use utils::game_settings::GameSettings;
-use game::GamePlugin;
-use game_over::GameOverPlugin;
-
-const GAME_TITLE: &str = env!("CARGO_PKG_NAME");
-const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
-static SETTINGS_STR: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/game_settings.toml"));
-
-
-#[derive(States, Default, Debug, Clone, Copy, Eq, PartialEq, Hash)]
-pub enum AppState {
-    #[default]
-    MainMenu,
-    Game,
-    GameOver,
-}
+use flowy::{AppState, GAME_TITLE, GAME_VERSION};
+use flowy::main_menu::MainMenuPlugin;
+use flowy::loading::LoadingPlugin;
+use flowy::utils::colours::{GamePallete, get_colour};
+use flowy::utils::game_settings::GameSettings;
+use flowy::utils::ui::scale_ui;
+use flowy::game::GamePlugin;
+use flowy::game_over::GameOverPlugin;
 
 
 fn main() {
 
     let game_settings = GameSettings::default();
+    let window_settings = &game_settings.window;
 
-    // TODO: use bevy asset loader for load screen.
-    App::new()
+    let mut app = App::new();
+    app
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())  // this reduces blur for pixel art
+                .set(AssetPlugin {
+                    // asset paths in the settings include the assets folder, like the level files
+                    file_path: String::from("."),
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: format!("{}_v{}", GAME_TITLE, GAME_VERSION),
-                        resolution: (game_settings.window.resolution.width as f32, game_settings.window.resolution.height as f32).into(),
-                        resizable: game_settings.window.resizable,
+                        resolution: (window_settings.resolution.width as f32, window_settings.resolution.height as f32).into(),
+                        resizable: window_settings.resizable,
                         ..default()
                     }),
                     ..default()
                 })
                 .build(),
         )
+        // loaded once here so the window and the game agree on the settings
+        .insert_resource(game_settings)
         .add_state::<AppState>()
         .add_plugins(MainMenuPlugin)
+        .add_plugins(LoadingPlugin)
         .add_plugins(GamePlugin)
         .add_plugins(GameOverPlugin)
         .add_systems(Startup, setup)
+        .add_systems(Update, (exit_game, scale_ui));
+
+    #[cfg(feature = "dev")]
+    app
         .add_systems(Update, transition_to_game_state)
-        .add_systems(Update, transition_to_main_menu_state)
-        .add_systems(Update, exit_game)
-        .run();
+        .add_systems(Update, transition_to_main_menu_state);
+
+    app.run();
 }
 
 
@@ -83,27 +81,26 @@ fn exit_game(
 }
 
 
-
+/// Debug shortcut to jump straight into the game, only built with the `dev` feature
+#[cfg(feature = "dev")]
 fn transition_to_game_state(
     input: Res<Input<KeyCode>>,
     mut app_state: ResMut<NextState<AppState>>,
 ) {
-    if input.just_pressed(KeyCode::G) {
-        if app_state.0 != Some(AppState::Game) {
-            app_state.set(AppState::Game);
-            println!("Entered AppState::Game");
-        }
+    if input.just_pressed(KeyCode::G) && app_state.0 != Some(AppState::Loading) {
+        app_state.set(AppState::Loading);
+        println!("Entered AppState::Loading");
     }
 }
 
+/// Debug shortcut to jump back to the main menu, only built with the `dev` feature
+#[cfg(feature = "dev")]
 fn transition_to_main_menu_state(
     input: Res<Input<KeyCode>>,
     mut app_state: ResMut<NextState<AppState>>,
 ) {
-    if input.just_pressed(KeyCode::M) {
-        if app_state.0 != Some(AppState::MainMenu) {
-            app_state.set(AppState::MainMenu);
-            println!("Entered AppState::MainMenu");
-        }
+    if input.just_pressed(KeyCode::M) && app_state.0 != Some(AppState::MainMenu) {
+        app_state.set(AppState::MainMenu);
+        println!("Entered AppState::MainMenu");
     }
 }
\ No newline at end of file
@@ -1,10 +1,203 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    app::AppExit,
+    window::PrimaryWindow,
+};
+
+use crate::utils::{
+    colours::GamePallete,
+    game_settings::GameSettings,
+    ui::{spawn_button, spawn_label, menu_root, highlight_buttons},
+};
+use crate::game::{Level, SelectedLevel, SaveGame, SaveFile, PendingSave};
+use crate::{AppState, GAME_TITLE, GAME_VERSION};
 
 
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app;
+        app
+            .init_resource::<MenuPage>()
+            .init_resource::<LevelNames>()
+            .add_systems(OnEnter(AppState::MainMenu), (load_level_names, open_main_page).chain())
+            .add_systems(Update, (highlight_buttons, handle_menu_buttons, render_menu_page.run_if(resource_changed::<MenuPage>()))
+                .chain()
+                .run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnExit(AppState::MainMenu), cleanup_menu);
+    }
+}
+
+
+/// Window sizes the settings page cycles through, each a whole number of cells
+const RESOLUTIONS: [(i32, i32); 4] = [(640, 640), (768, 768), (960, 960), (1280, 768)];
+
+
+/// The page of the main menu currently on screen
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuPage {
+    #[default]
+    Main,
+    LevelSelect,
+    Settings,
+}
+
+/// The id and display name of each level in the settings, read from the level files once when the menu opens
+#[derive(Resource, Default, Debug)]
+pub struct LevelNames(pub Vec<(String, String)>);
+
+/// Identifies the root of the menu so the whole page can be swapped out
+#[derive(Component)]
+pub struct MenuScreen;
+
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub enum MenuButton {
+    Continue,
+    Play,
+    LevelSelect,
+    Settings,
+    Quit,
+    Back,
+    Level(String),
+    Resolution,
+}
+
+
+fn load_level_names(
+    game_settings: Res<GameSettings>,
+    mut level_names: ResMut<LevelNames>,
+) {
+    level_names.0 = game_settings.levels
+        .iter()
+        .map(|(id, level_meta)| {
+            let name = Level::load(id, level_meta)
+                .map(|level| level.name)
+                .unwrap_or_else(|_| id.clone());
+            (id.clone(), name)
+        })
+        .collect();
+}
+
+
+fn open_main_page(
+    mut menu_page: ResMut<MenuPage>,
+) {
+    // always mark as changed so the page is drawn when coming back from another state
+    *menu_page = MenuPage::Main;
+}
+
+
+fn render_menu_page(
+    mut commands: Commands,
+    game_settings: Res<GameSettings>,
+    save_file: Res<SaveFile>,
+    level_names: Res<LevelNames>,
+    menu_page: Res<MenuPage>,
+    q_menu_screen: Query<Entity, With<MenuScreen>>,
+) {
+    for entity in q_menu_screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((menu_root(), MenuScreen))
+        .with_children(|parent| {
+            spawn_label(parent, GAME_TITLE, 80.0, GamePallete::JapaneseIndigo);
+            spawn_label(parent, format!("v{}", GAME_VERSION), 20.0, GamePallete::Feldgrau);
+
+            match *menu_page {
+                MenuPage::Main => {
+                    if save_file.exists() {
+                        spawn_button(parent, "Continue", MenuButton::Continue);
+                    }
+                    spawn_button(parent, "Play", MenuButton::Play);
+                    spawn_button(parent, "Level Select", MenuButton::LevelSelect);
+                    spawn_button(parent, "Settings", MenuButton::Settings);
+                    spawn_button(parent, "Quit", MenuButton::Quit);
+                },
+                MenuPage::LevelSelect => {
+                    for (number, (id, name)) in level_names.0.iter().enumerate() {
+                        spawn_button(parent, &format!("{}. {}", number + 1, name), MenuButton::Level(id.clone()));
+                    }
+                    spawn_button(parent, "Back", MenuButton::Back);
+                },
+                MenuPage::Settings => {
+                    let resolution = &game_settings.window.resolution;
+                    spawn_button(parent, &format!("Window: {}x{}", resolution.width, resolution.height), MenuButton::Resolution);
+                    spawn_button(parent, "Back", MenuButton::Back);
+                },
+            }
+        });
+}
+
+
+#[allow(clippy::too_many_arguments)]
+fn handle_menu_buttons(
+    mut game_settings: ResMut<GameSettings>,
+    save_file: Res<SaveFile>,
+    mut selected_level: ResMut<SelectedLevel>,
+    mut pending_save: ResMut<PendingSave>,
+    mut menu_page: ResMut<MenuPage>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut exit_writer: EventWriter<AppExit>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+    q_buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in q_buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MenuButton::Continue => match SaveGame::read(&save_file.0) {
+                Ok(save_game) => {
+                    selected_level.id = save_game.level_id.clone();
+                    pending_save.0 = Some(save_game);
+                    app_state.set(AppState::Loading);
+                },
+                Err(err) => println!("Failed to load game: {}", err),
+            },
+            MenuButton::Play => {
+                // always start from the first level, even after playing or continuing another one
+                selected_level.id = game_settings.levels.keys().next().cloned();
+                app_state.set(AppState::Loading);
+            },
+            MenuButton::LevelSelect => *menu_page = MenuPage::LevelSelect,
+            MenuButton::Settings => *menu_page = MenuPage::Settings,
+            MenuButton::Quit => exit_writer.send(AppExit),
+            MenuButton::Back => *menu_page = MenuPage::Main,
+            MenuButton::Level(id) => {
+                selected_level.id = Some(id.clone());
+                app_state.set(AppState::Loading);
+            },
+            MenuButton::Resolution => {
+                let resolution = &mut game_settings.window.resolution;
+                let current = RESOLUTIONS
+                    .iter()
+                    .position(|size| *size == (resolution.width, resolution.height))
+                    .unwrap_or(0);
+                let (width, height) = RESOLUTIONS[(current + 1) % RESOLUTIONS.len()];
+                resolution.width = width;
+                resolution.height = height;
+
+                if let Ok(mut window) = q_window.get_single_mut() {
+                    window.resolution.set(width as f32, height as f32);
+                }
+                if let Err(err) = game_settings.save_user_settings() {
+                    println!("Failed to save settings: {}", err);
+                }
+                // redraw the page so the button shows the new size
+                menu_page.set_changed();
+            },
+        }
+    }
+}
+
+
+fn cleanup_menu(
+    mut commands: Commands,
+    q_menu_screen: Query<Entity, With<MenuScreen>>,
+) {
+    for entity in q_menu_screen.iter() {
+        commands.entity(entity).despawn_recursive();
     }
-}
\ No newline at end of file
+}
@@ -1,30 +1,85 @@
-use bevy::prelude::Color;
-
-
-pub enum AssetType {
-    StraightPipe,
-    Bend,
-    Tee,
-    Quad,
-    Pump,
-    PumpStation,
-    Home,
-    Business
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    asset::LoadState,
+};
+
+use crate::flowy_core::equipment::EquipmentKind;
+use crate::utils::{
+    colours::{GamePallete, get_colour},
+    game_settings::AssetSettings,
+};
+
+
+/// The textures for the `[assets]` settings table, keyed by asset name. Assets without a `path` have no entry.
+#[derive(Resource, Default, Debug)]
+pub struct AssetHandles(pub HashMap<String, Handle<Image>>);
+
+impl AssetHandles {
+    /// Start loading the texture of every asset that has one. A texture that isn't under the asset root is reported
+    /// as a warning and left out, so the asset is drawn in its colour instead.
+    pub fn load(asset_settings: &AssetSettings, asset_server: &AssetServer) -> Self {
+        let handles = asset_settings
+            .iter()
+            .into_iter()
+            .filter_map(|(_, asset_meta)| {
+                let path = asset_meta.path.as_ref()?;
+                if let Err(err) = asset_meta.check_path() {
+                    println!("Warning: {}, drawing it in its colour", err);
+                    return None;
+                }
+                Some((asset_meta.name.clone(), asset_server.load(path.clone())))
+            })
+            .collect();
+
+        AssetHandles(handles)
+    }
+
+    /// How many textures have either loaded or failed to, a failed texture is drawn in its colour instead
+    pub fn settled(&self, asset_server: &AssetServer) -> usize {
+        self.0
+            .values()
+            .filter(|handle| matches!(asset_server.get_load_state(*handle), Some(LoadState::Loaded | LoadState::Failed)))
+            .count()
+    }
 }
 
 
-pub fn get_asset(color: AssetType) -> Color {
-    // TODO: change to return asset
-    let hex = match color {
-        AssetType::StraightPipe => "bbb094",
-        AssetType::Bend => "807665",
-        AssetType::Tee => "595246",
-        AssetType::Quad => "333333",
-        AssetType::Pump => "191f22",
-        AssetType::PumpStation => "2f4443",
-        AssetType::Home => "3b5e58",
-        AssetType::Business => "5a8c6c",
+/// How to draw an entry in the `[assets]` settings table
+pub enum AssetSprite {
+    Texture(Handle<Image>),
+    Colour(Color),
+}
+
+
+/// Get the asset for a kind of equipment, falling back to its colour when it has no texture or the texture couldn't
+/// be loaded
+pub fn get_asset(
+    kind: EquipmentKind,
+    asset_settings: &AssetSettings,
+    asset_handles: &AssetHandles,
+    asset_server: &AssetServer,
+) -> AssetSprite {
+    match asset_handles.0.get(&kind.asset_meta(asset_settings).name) {
+        Some(handle) if asset_server.get_load_state(handle) == Some(LoadState::Loaded) => AssetSprite::Texture(handle.clone()),
+        _ => AssetSprite::Colour(asset_colour(kind)),
+    }
+}
+
+
+/// The colour a kind of equipment is drawn in when it has no texture
+pub fn asset_colour(kind: EquipmentKind) -> Color {
+    let colour = match kind {
+        EquipmentKind::StraightPipe => GamePallete::Khaki,
+        EquipmentKind::Bend => GamePallete::Shadow,
+        EquipmentKind::Tee => GamePallete::Umber,
+        EquipmentKind::Quad => GamePallete::DarkCharcoal,
+        EquipmentKind::Pump => GamePallete::DarkJungleGreen,
+        EquipmentKind::PumpStation => GamePallete::JapaneseIndigo,
+        EquipmentKind::Home => GamePallete::Feldgrau,
+        EquipmentKind::Business => GamePallete::RussianGreen,
     };
 
-    Color::hex(hex).unwrap_or(Color::rgb(0.0,0.0,0.0))
-}
\ No newline at end of file
+    get_colour(colour)
+}
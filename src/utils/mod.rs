@@ -1,3 +1,4 @@
 pub mod colours;
 pub mod game_settings;
-pub mod assets;
\ No newline at end of file
+pub mod assets;
+pub mod ui;
@@ -0,0 +1,137 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+
+use crate::utils::colours::{GamePallete, get_colour};
+
+
+const BUTTON_WIDTH: f32 = 320.0;
+const BUTTON_HEIGHT: f32 = 52.0;
+/// The window size the UI is laid out for, larger or smaller windows scale it up or down
+const UI_REFERENCE_SIZE: Vec2 = Vec2::new(640.0, 640.0);
+
+type ChangedButtonFilter = (Changed<Interaction>, With<Button>);
+
+
+/// Spawn a menu button with a label, tagged with a marker component to say what it does
+pub fn spawn_button(parent: &mut ChildBuilder, label: &str, marker: impl Component) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(BUTTON_WIDTH),
+                    height: Val::Px(BUTTON_HEIGHT),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: get_colour(GamePallete::Feldgrau).into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: Default::default(),
+                    font_size: 40.0,
+                    color: get_colour(GamePallete::Blond),
+                },
+            ));
+        });
+}
+
+
+/// Spawn a line of text in a menu
+pub fn spawn_label(parent: &mut ChildBuilder, text: impl Into<String>, font_size: f32, colour: GamePallete) {
+    parent.spawn(TextBundle::from_section(
+        text,
+        TextStyle {
+            font: Default::default(),
+            font_size,
+            color: get_colour(colour),
+        },
+    ));
+}
+
+
+/// A full screen column that centres whatever is spawned inside it
+pub fn menu_root() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+
+/// The corner of the screen a piece of HUD text is pinned to
+pub enum HudCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Pin HUD text to a corner of the screen, wrapping it on to more lines if the window gets too narrow
+pub fn hud_style(corner: HudCorner) -> Style {
+    let margin = Val::Px(8.0);
+    let (top, right, bottom, left) = match corner {
+        HudCorner::TopLeft => (margin, Val::Auto, Val::Auto, margin),
+        HudCorner::TopRight => (margin, margin, Val::Auto, Val::Auto),
+        HudCorner::BottomLeft => (Val::Auto, Val::Auto, margin, margin),
+        HudCorner::BottomRight => (Val::Auto, margin, margin, Val::Auto),
+    };
+
+    Style {
+        position_type: PositionType::Absolute,
+        top,
+        right,
+        bottom,
+        left,
+        max_width: Val::Percent(50.0),
+        ..default()
+    }
+}
+
+
+/// Scale all the UI with the window so text stays readable on small screens and in proportion on large ones
+pub fn scale_ui(
+    mut resize_events: EventReader<WindowResized>,
+    mut ui_scale: ResMut<UiScale>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let resized = resize_events.read().count() > 0;
+    // the window may open at a size other than the reference without ever being resized
+    if !resized && !ui_scale.is_added() {
+        return;
+    }
+
+    if let Ok(window) = q_window.get_single() {
+        let scale = (window.width() / UI_REFERENCE_SIZE.x).min(window.height() / UI_REFERENCE_SIZE.y);
+        ui_scale.0 = scale.max(0.25) as f64;
+    }
+}
+
+
+/// Change the colour of buttons as they are hovered and pressed
+pub fn highlight_buttons(
+    mut q_buttons: Query<(&Interaction, &mut BackgroundColor), ChangedButtonFilter>,
+) {
+    for (interaction, mut background_colour) in q_buttons.iter_mut() {
+        *background_colour = match interaction {
+            Interaction::Pressed => get_colour(GamePallete::JapaneseIndigo).into(),
+            Interaction::Hovered => get_colour(GamePallete::HookersGreen).into(),
+            Interaction::None => get_colour(GamePallete::Feldgrau).into(),
+        };
+    }
+}
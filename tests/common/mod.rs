@@ -0,0 +1,251 @@
+//! A headless app for driving the game from tests.
+//!
+//! The app is built with `MinimalPlugins` and just enough of Bevy for the game's systems to run: a window that is
+//! never opened, so the cursor can be put over it, and the camera maths, so the cursor is picked through the camera
+//! like it is in the game. Input is injected straight into the `Input` resources and frames are stepped by hand.
+
+use std::path::PathBuf;
+
+use bevy::{
+    prelude::*,
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    render::camera::CameraPlugin,
+    window::{ExitCondition, PrimaryWindow},
+};
+
+use flowy::AppState;
+use flowy::flowy_core::{budget::{Budget, BudgetMode}, grid::{Cell, CellId, Grid, GridSettings}, level::Terrain};
+use flowy::game::{BudgetText, CursorGridIdx, CursorModeText, GamePlugin, Ghost, Ledger, LedgerPanel, Level, SaveFile, SimulationState, SpawnedEquipment};
+use flowy::loading::LoadingPlugin;
+use flowy::utils::{assets::AssetHandles, game_settings::GameSettings};
+
+
+/// How many frames to wait for something to happen before giving up
+const MAX_FRAMES: usize = 100;
+
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Harness {
+    /// An app sitting on the main menu, with the built in settings changed by `configure` and a blank board instead
+    /// of the first level
+    pub fn with_settings(configure: impl FnOnce(&mut GameSettings)) -> Self {
+        let mut game_settings = GameSettings::built_in();
+        configure(&mut game_settings);
+        // without any levels the game falls back to a blank grass board the size of the window
+        game_settings.levels.clear();
+        let resolution = &game_settings.window.resolution;
+        let window = Window {
+            resolution: (resolution.width as f32, resolution.height as f32).into(),
+            ..default()
+        };
+
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugins((
+                TransformPlugin,
+                // asset paths in the settings include the assets folder, the same as the game
+                AssetPlugin { file_path: String::from("."), ..default() },
+                WindowPlugin {
+                    primary_window: Some(window),
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                },
+                CameraPlugin,
+            ))
+            .init_asset::<Image>()
+            // input is injected by hand rather than read from window events
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .add_event::<MouseWheel>()
+            .add_event::<MouseMotion>()
+            // inserted before the game plugin so the player's own settings are never loaded
+            .insert_resource(game_settings)
+            .add_state::<AppState>()
+            .add_plugins((LoadingPlugin, GamePlugin));
+        app.world.spawn(Camera2dBundle::default());
+        app.update();
+
+        Self { app }
+    }
+
+    /// An app part way through a level with the simulation running and the cursor in no mode
+    pub fn in_game() -> Self {
+        Self::in_game_with_settings(|_| {})
+    }
+
+    /// Like `in_game`, with the built in settings changed first
+    pub fn in_game_with_settings(configure: impl FnOnce(&mut GameSettings)) -> Self {
+        let mut harness = Self::with_settings(configure);
+        harness.app.world.resource_mut::<NextState<AppState>>().set(AppState::Loading);
+        harness.step_until(|harness| harness.app_state() == AppState::Game);
+        // levels start paused
+        harness.tap_key(KeyCode::Space);
+        harness.step_until(|harness| harness.simulation_state() == SimulationState::Running);
+        harness
+    }
+
+    /// Switch the level being played between blocking and allowing placements that can't be afforded
+    pub fn set_budget_mode(&mut self, budget_mode: BudgetMode) {
+        self.app.world.resource_mut::<Level>().budget_mode = budget_mode;
+    }
+
+    /// Quicksave to a file of the test's own instead of the player's
+    pub fn set_save_file(&mut self, path: PathBuf) {
+        self.app.world.insert_resource(SaveFile(path));
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Step frames until the condition holds, failing the test if it never does
+    pub fn step_until(&mut self, condition: impl Fn(&Harness) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if condition(self) {
+                return;
+            }
+            self.app.update();
+        }
+        assert!(condition(self), "condition not met after {} frames", MAX_FRAMES);
+    }
+
+    /// Press and release a key over a single frame
+    pub fn tap_key(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(key);
+        self.app.update();
+        let mut input = self.app.world.resource_mut::<Input<KeyCode>>();
+        input.release(key);
+        input.clear();
+    }
+
+    /// Press a key while holding Ctrl, releasing both after a single frame
+    pub fn tap_ctrl_key(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::ControlLeft);
+        self.tap_key(key);
+        self.app.world.resource_mut::<Input<KeyCode>>().reset(KeyCode::ControlLeft);
+    }
+
+    /// Press and release a mouse button over a single frame
+    pub fn click(&mut self, button: MouseButton) {
+        self.app.world.resource_mut::<Input<MouseButton>>().press(button);
+        self.app.update();
+        let mut input = self.app.world.resource_mut::<Input<MouseButton>>();
+        input.release(button);
+        input.clear();
+    }
+
+    /// Turn the mouse wheel a number of notches, positive being away from the player
+    pub fn scroll(&mut self, notches: f32) {
+        let mut q_window = self.app.world.query_filtered::<Entity, With<PrimaryWindow>>();
+        let window = q_window.single(&self.app.world);
+        self.app.world.send_event(MouseWheel { unit: MouseScrollUnit::Line, x: 0.0, y: notches, window });
+        self.app.update();
+    }
+
+    /// Put the cursor over the centre of a cell, projected through the camera like a real mouse position
+    pub fn move_cursor_to(&mut self, cell_idx: CellId) {
+        let centre = self.grid().cells[&cell_idx].centre;
+        let world_position = self.app.world.resource::<GridSettings>().cell_to_world(&centre);
+
+        let mut q_camera = self.app.world.query::<(&Camera, &GlobalTransform)>();
+        let (camera, camera_transform) = q_camera.single(&self.app.world);
+        let cursor_position = camera
+            .world_to_viewport(camera_transform, world_position.extend(0.0))
+            .expect("camera has not been sized to the window yet");
+
+        let mut q_window = self.app.world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        q_window.single_mut(&mut self.app.world).set_cursor_position(Some(cursor_position));
+        self.app.update();
+    }
+
+    pub fn move_cursor_off_window(&mut self) {
+        let mut q_window = self.app.world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        q_window.single_mut(&mut self.app.world).set_cursor_position(None);
+        self.app.update();
+    }
+
+    pub fn hovered_cell(&self) -> Option<CellId> {
+        self.app.world.resource::<CursorGridIdx>().index
+    }
+
+    pub fn app_state(&self) -> AppState {
+        *self.app.world.resource::<State<AppState>>().get()
+    }
+
+    pub fn simulation_state(&self) -> SimulationState {
+        *self.app.world.resource::<State<SimulationState>>().get()
+    }
+
+    pub fn grid(&self) -> &Grid {
+        self.app.world.resource::<Grid>()
+    }
+
+    pub fn cell(&self, cell_idx: CellId) -> &Cell {
+        &self.grid().cells[&cell_idx]
+    }
+
+    /// Change the ground under a cell of the blank board
+    pub fn set_terrain(&mut self, cell_idx: CellId, terrain: Terrain) {
+        self.app.world.resource_mut::<Grid>().cells.get_mut(&cell_idx).unwrap().terrain = terrain;
+    }
+
+    pub fn settings(&self) -> &GameSettings {
+        self.app.world.resource::<GameSettings>()
+    }
+
+    pub fn asset_handles(&self) -> &AssetHandles {
+        self.app.world.resource::<AssetHandles>()
+    }
+
+    /// How many pieces of equipment have been drawn on the grid
+    pub fn spawned_equipment(&mut self) -> usize {
+        self.app.world.query_filtered::<(), With<SpawnedEquipment>>().iter(&self.app.world).count()
+    }
+
+    pub fn budget(&self) -> i32 {
+        self.app.world.resource::<Budget>().0
+    }
+
+    /// The message shown under the budget, empty when there isn't one
+    pub fn budget_message(&mut self) -> String {
+        let mut q_text = self.app.world.query_filtered::<&Text, With<BudgetText>>();
+        q_text.single(&self.app.world).sections[2].value.trim().to_string()
+    }
+
+    /// The preview drawn under the cursor, if there is one
+    pub fn ghost(&mut self) -> Option<Ghost> {
+        let mut q_ghost = self.app.world.query::<&Ghost>();
+        q_ghost.get_single(&self.app.world).ok().copied()
+    }
+
+    pub fn set_budget(&mut self, budget: i32) {
+        self.app.world.resource_mut::<Budget>().0 = budget;
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        self.app.world.resource::<Ledger>()
+    }
+
+    pub fn ledger_panel_visible(&mut self) -> bool {
+        let mut q_panel = self.app.world.query_filtered::<&Visibility, With<LedgerPanel>>();
+        *q_panel.single(&self.app.world) != Visibility::Hidden
+    }
+
+    /// The mode shown on the cursor mode text, e.g. "Placing"
+    pub fn cursor_mode(&mut self) -> String {
+        let mut q_text = self.app.world.query_filtered::<&Text, With<CursorModeText>>();
+        q_text.single(&self.app.world).sections[1].value.clone()
+    }
+
+    /// The selected equipment and its cost shown on the cursor mode text, e.g. "Straight Pipe (1)"
+    pub fn selected_equipment(&mut self) -> String {
+        let mut q_text = self.app.world.query_filtered::<&Text, With<CursorModeText>>();
+        q_text.single(&self.app.world).sections[3].value.clone()
+    }
+}
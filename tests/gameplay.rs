@@ -0,0 +1,523 @@
+mod common;
+
+use bevy::prelude::*;
+
+use common::Harness;
+use flowy::AppState;
+use flowy::flowy_core::{budget::BudgetMode, equipment::{EquipmentKind, Placement}, grid::CellId, ledger::{CostCategory, Transaction}, level::Terrain};
+use flowy::game::{GameOver, SimulationState, evaluation::{LevelOutcome, LevelResult}};
+use flowy::utils::assets::{AssetSprite, asset_colour, get_asset};
+
+
+/// What a straight pipe costs on grass with the built in settings
+const PIPE_COST: i32 = 1;
+const BEND_COST: i32 = 2;
+const START_BUDGET: i32 = 10;
+/// The built in `gameplay.overdraft_penalty`
+const OVERDRAFT_PENALTY: i32 = 5;
+
+
+/// Lay a pipe in one more cell than the starting budget pays for, working along the rows from the top left
+fn overspend(harness: &mut Harness) -> CellId {
+    let cells = (0..=START_BUDGET / PIPE_COST)
+        .map(|i| CellId::new(i % 10, i / 10))
+        .collect::<Vec<_>>();
+
+    harness.click(MouseButton::Left);
+    for cell_idx in cells.iter() {
+        harness.move_cursor_to(*cell_idx);
+    }
+    harness.step(3);
+
+    *cells.last().unwrap()
+}
+
+
+#[test]
+fn loading_builds_the_grid_and_starts_the_level() {
+    let harness = Harness::in_game();
+
+    assert_eq!(harness.app_state(), AppState::Game);
+    assert_eq!(harness.grid().cells.ids().len(), 100);
+    assert_eq!(harness.budget(), START_BUDGET);
+}
+
+
+#[test]
+fn a_missing_texture_is_drawn_in_its_colour() {
+    let harness = Harness::in_game_with_settings(|game_settings| {
+        game_settings.assets.straight_pipe.path = Some(String::from("assets/pipe.png"));
+        game_settings.assets.tee.path = Some(String::from("assets/does_not_exist.png"));
+    });
+
+    // the level still loads, with only the texture that is there being loaded
+    assert_eq!(harness.app_state(), AppState::Game);
+    assert!(harness.asset_handles().0.contains_key("straight_pipe"));
+    assert!(!harness.asset_handles().0.contains_key("tee"));
+
+    let asset_server = harness.app.world.resource::<AssetServer>();
+    let sprite = get_asset(EquipmentKind::Tee, &harness.settings().assets, harness.asset_handles(), asset_server);
+    assert!(matches!(sprite, AssetSprite::Colour(colour) if colour == asset_colour(EquipmentKind::Tee)));
+}
+
+
+#[test]
+fn space_pauses_and_resumes_the_simulation() {
+    let mut harness = Harness::in_game();
+
+    harness.tap_key(KeyCode::Space);
+    harness.step_until(|harness| harness.simulation_state() == SimulationState::Paused);
+    harness.tap_key(KeyCode::Space);
+    harness.step_until(|harness| harness.simulation_state() == SimulationState::Running);
+}
+
+
+#[test]
+fn left_click_toggles_placing_mode() {
+    let mut harness = Harness::in_game();
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "None");
+
+    harness.click(MouseButton::Left);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "Placing");
+
+    harness.click(MouseButton::Left);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "None");
+}
+
+
+#[test]
+fn right_click_toggles_deleting_mode() {
+    let mut harness = Harness::in_game();
+
+    harness.click(MouseButton::Right);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "Deleting");
+
+    harness.click(MouseButton::Right);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "None");
+}
+
+
+#[test]
+fn only_one_mode_is_active_at_a_time() {
+    let mut harness = Harness::in_game();
+
+    harness.click(MouseButton::Left);
+    harness.click(MouseButton::Right);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "Placing");
+
+    harness.click(MouseButton::Left);
+    harness.click(MouseButton::Right);
+    harness.click(MouseButton::Left);
+    harness.step(1);
+    assert_eq!(harness.cursor_mode(), "Deleting");
+}
+
+
+#[test]
+fn placing_occupies_the_cell_and_charges_the_budget() {
+    let mut harness = Harness::in_game();
+    let cell_idx = CellId::new(2, 3);
+
+    harness.move_cursor_to(cell_idx);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+
+    assert!(harness.cell(cell_idx).occupied);
+}
+
+
+#[test]
+fn the_selected_fitting_is_laid_and_charged() {
+    let mut harness = Harness::in_game();
+    let cell_idx = CellId::new(2, 3);
+
+    // straight pipe is selected to start with and the bend is next
+    harness.scroll(1.0);
+    harness.move_cursor_to(cell_idx);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - BEND_COST);
+
+    let kinds = harness.ledger().entries().iter().map(|entry| entry.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![EquipmentKind::Bend]);
+}
+
+
+#[test]
+fn dragging_with_a_fitting_selected_lays_the_path_that_joins_up() {
+    let mut harness = Harness::in_game();
+    let cells = [CellId::new(2, 3), CellId::new(3, 3), CellId::new(4, 3)];
+
+    harness.scroll(1.0);
+    harness.scroll(1.0);
+    harness.step(1);
+    assert!(harness.selected_equipment().starts_with("Tee"));
+
+    // a tee in each cell would leave a port open to leak, so a straight run is laid and charged
+    drag_stroke(&mut harness, &cells);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - 3 * PIPE_COST);
+    let kinds = harness.ledger()
+        .entries()
+        .iter()
+        .filter(|entry| entry.transaction == Transaction::Charge)
+        .map(|entry| entry.kind)
+        .collect::<Vec<_>>();
+    assert!(kinds.ends_with(&[EquipmentKind::StraightPipe; 3]), "{:?}", kinds);
+}
+
+#[test]
+fn deleting_frees_the_cell_and_refunds_the_budget() {
+    let mut harness = Harness::in_game();
+    let cell_idx = CellId::new(2, 3);
+
+    harness.move_cursor_to(cell_idx);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+    harness.click(MouseButton::Left);
+
+    harness.click(MouseButton::Right);
+    harness.step_until(|harness| harness.budget() == START_BUDGET);
+
+    assert!(!harness.cell(cell_idx).occupied);
+}
+
+
+#[test]
+fn loading_a_quicksave_restores_the_network_budget_and_ledger() {
+    let mut harness = Harness::in_game();
+    let save_file = std::env::temp_dir().join(format!("flowy_quicksave_{}.toml", std::process::id()));
+    harness.set_save_file(save_file.clone());
+    let pipe = CellId::new(2, 3);
+    let bend = CellId::new(6, 6);
+
+    harness.move_cursor_to(pipe);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+    harness.click(MouseButton::Left);
+    harness.scroll(1.0);
+    harness.move_cursor_to(bend);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST - BEND_COST);
+    harness.click(MouseButton::Left);
+    let charges = |harness: &Harness| harness.ledger()
+        .entries()
+        .iter()
+        .filter(|entry| entry.transaction == Transaction::Charge)
+        .map(|entry| (entry.kind, entry.cell_idx, entry.cost))
+        .collect::<Vec<_>>();
+    let saved_charges = charges(&harness);
+
+    harness.tap_key(KeyCode::F5);
+    assert!(save_file.exists());
+
+    // clear the board back to the starting budget
+    harness.move_cursor_to(pipe);
+    harness.click(MouseButton::Right);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - BEND_COST);
+    harness.move_cursor_to(bend);
+    harness.step_until(|harness| harness.budget() == START_BUDGET);
+    harness.click(MouseButton::Right);
+    assert!(!harness.cell(pipe).occupied && !harness.cell(bend).occupied);
+
+    harness.tap_key(KeyCode::F9);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST - BEND_COST);
+    harness.step(2);
+
+    let occupied = harness.grid().cells.values().filter(|cell| cell.occupied).map(|cell| cell.id).collect::<Vec<_>>();
+    assert_eq!(occupied.len(), 2);
+    assert!(harness.cell(pipe).occupied && harness.cell(bend).occupied);
+    assert_eq!(harness.budget(), START_BUDGET - PIPE_COST - BEND_COST);
+    assert_eq!(charges(&harness), saved_charges);
+    assert_eq!(harness.ledger().entries().len(), saved_charges.len());
+    std::fs::remove_file(save_file).unwrap();
+}
+
+
+#[test]
+fn a_quicksave_loaded_while_paused_is_drawn_straight_away() {
+    let mut harness = Harness::in_game();
+    let save_file = std::env::temp_dir().join(format!("flowy_paused_quicksave_{}.toml", std::process::id()));
+    harness.set_save_file(save_file.clone());
+    let pipe = CellId::new(2, 3);
+
+    harness.move_cursor_to(pipe);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+    harness.click(MouseButton::Left);
+    harness.tap_key(KeyCode::F5);
+
+    // continuing from the main menu also starts paused
+    harness.tap_key(KeyCode::Space);
+    harness.step_until(|harness| harness.simulation_state() == SimulationState::Paused);
+    harness.tap_key(KeyCode::F9);
+    harness.step(2);
+
+    assert_eq!(harness.simulation_state(), SimulationState::Paused);
+    assert!(harness.cell(pipe).occupied);
+    assert_eq!(harness.spawned_equipment(), 1);
+    std::fs::remove_file(save_file).unwrap();
+}
+
+
+#[test]
+fn a_hard_cap_blocks_what_cannot_be_afforded() {
+    let mut harness = Harness::in_game();
+
+    let unaffordable = overspend(&mut harness);
+
+    assert_eq!(harness.budget(), 0);
+    assert!(!harness.cell(unaffordable).occupied);
+    assert_eq!(harness.ghost().map(|ghost| (ghost.cell_idx, ghost.placement)), Some((unaffordable, Placement::Unaffordable)));
+    assert_eq!(harness.budget_message(), "Insufficient funds");
+    assert_eq!(harness.app_state(), AppState::Game);
+}
+
+
+#[test]
+fn an_overdraft_lets_the_budget_go_negative_and_carries_on() {
+    let mut harness = Harness::in_game();
+    harness.set_budget_mode(BudgetMode::Overdraft);
+
+    let overdrawn = overspend(&mut harness);
+
+    assert_eq!(harness.budget(), START_BUDGET - 11 * PIPE_COST);
+    assert!(harness.cell(overdrawn).occupied);
+    assert_eq!(harness.ghost(), None);
+    assert_eq!(harness.budget_message(), "");
+    assert_eq!(harness.app_state(), AppState::Game);
+}
+
+
+#[test]
+fn the_ledger_records_charges_and_refunds() {
+    let mut harness = Harness::in_game();
+    let cell_idx = CellId::new(2, 3);
+
+    harness.move_cursor_to(cell_idx);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+    harness.click(MouseButton::Left);
+    harness.click(MouseButton::Right);
+    harness.step_until(|harness| harness.budget() == START_BUDGET);
+
+    let transactions = harness.ledger()
+        .entries()
+        .iter()
+        .map(|entry| (entry.transaction, entry.cell_idx, entry.cost))
+        .collect::<Vec<_>>();
+    assert_eq!(transactions, vec![
+        (Transaction::Charge, cell_idx, PIPE_COST),
+        (Transaction::Refund, cell_idx, PIPE_COST),
+    ]);
+    assert_eq!(harness.ledger().total(CostCategory::Pipes), 0);
+}
+
+
+/// Lay a straight run of pipe along a row in one stroke
+fn drag_stroke(harness: &mut Harness, cells: &[CellId]) {
+    harness.move_cursor_to(cells[0]);
+    // placing starts the frame after the click
+    harness.click(MouseButton::Left);
+    harness.step(1);
+    for cell_idx in &cells[1..] {
+        // a piece takes a couple of frames to be drawn, and the next one only joins on to it once it is
+        harness.step(2);
+        harness.move_cursor_to(*cell_idx);
+    }
+    harness.click(MouseButton::Left);
+    harness.step(1);
+}
+
+/// Which of the cells are taken, the budget and what the ledger says has been spent
+fn snapshot(harness: &Harness, cells: &[CellId]) -> (Vec<bool>, i32, i32, i32) {
+    let occupied = cells.iter().map(|cell_idx| harness.cell(*cell_idx).occupied).collect();
+    (occupied, harness.budget(), harness.ledger().spent(), harness.ledger().total(CostCategory::Pipes))
+}
+
+
+#[test]
+fn undo_and_redo_step_a_whole_stroke() {
+    let mut harness = Harness::in_game();
+    let cells = [CellId::new(2, 3), CellId::new(3, 3), CellId::new(4, 3)];
+
+    let before = snapshot(&harness, &cells);
+    drag_stroke(&mut harness, &cells);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - 3 * PIPE_COST);
+    let after = snapshot(&harness, &cells);
+    assert_eq!(after.0, vec![true; 3]);
+
+    harness.tap_ctrl_key(KeyCode::Z);
+    harness.step_until(|harness| harness.budget() == before.1);
+    assert_eq!(snapshot(&harness, &cells), before);
+
+    harness.tap_ctrl_key(KeyCode::Y);
+    harness.step_until(|harness| harness.budget() == after.1);
+    assert_eq!(snapshot(&harness, &cells), after);
+}
+
+
+#[test]
+fn undo_refunds_what_the_stroke_was_charged() {
+    let mut harness = Harness::in_game();
+    let cells = [CellId::new(2, 3), CellId::new(3, 3), CellId::new(4, 3)];
+    for cell_idx in cells {
+        harness.set_terrain(cell_idx, Terrain::Road);
+    }
+
+    // roads cost twice as much to dig up
+    let before = snapshot(&harness, &cells);
+    drag_stroke(&mut harness, &cells);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - 3 * 2 * PIPE_COST);
+    let after = snapshot(&harness, &cells);
+
+    // once laid the pipe is refunded what it cost, not what the ground under it would cost now
+    for cell_idx in cells {
+        harness.set_terrain(cell_idx, Terrain::Grass);
+    }
+    harness.tap_ctrl_key(KeyCode::Z);
+    harness.step_until(|harness| harness.budget() == before.1);
+    assert_eq!(snapshot(&harness, &cells), before);
+
+    harness.tap_ctrl_key(KeyCode::Y);
+    harness.step_until(|harness| harness.budget() == after.1);
+    assert_eq!(snapshot(&harness, &cells), after);
+}
+
+
+#[test]
+fn l_toggles_the_ledger_panel() {
+    let mut harness = Harness::in_game();
+    assert!(!harness.ledger_panel_visible());
+
+    harness.tap_key(KeyCode::L);
+    assert!(harness.ledger_panel_visible());
+
+    harness.tap_key(KeyCode::L);
+    assert!(!harness.ledger_panel_visible());
+}
+
+
+#[test]
+fn the_level_result_keeps_the_ledger_and_takes_off_the_overdraft() {
+    let mut harness = Harness::in_game();
+    harness.set_budget_mode(BudgetMode::Overdraft);
+
+    overspend(&mut harness);
+    harness.app.world.send_event(GameOver { outcome: LevelOutcome::OutOfTime });
+    harness.step_until(|harness| harness.app_state() == AppState::GameOver);
+
+    let level_result = harness.app.world.resource::<LevelResult>();
+    assert_eq!(level_result.money_spent, 11 * PIPE_COST);
+    assert_eq!(level_result.ledger.spent(), level_result.money_spent);
+    assert_eq!(level_result.ledger.invoice().iter().map(|line| line.total).sum::<i32>(), level_result.money_spent);
+    assert_eq!(level_result.overdraft, 1);
+    assert_eq!(level_result.score, -OVERDRAFT_PENALTY);
+}
+
+
+#[test]
+fn hovering_previews_the_selected_equipment_and_its_cost() {
+    let mut harness = Harness::in_game();
+    let cell_idx = CellId::new(4, 4);
+
+    harness.move_cursor_to(cell_idx);
+    harness.step(1);
+
+    let ghost = harness.ghost().unwrap();
+    assert_eq!((ghost.cell_idx, ghost.kind, ghost.cost), (cell_idx, EquipmentKind::StraightPipe, PIPE_COST));
+    assert_eq!(ghost.placement, Placement::Placeable);
+}
+
+
+#[test]
+fn scrolling_to_a_fitting_previews_it_while_hovering_and_dragging() {
+    let mut harness = Harness::in_game();
+    let start = CellId::new(3, 4);
+    let plot = CellId::new(4, 4);
+    harness.set_terrain(plot, Terrain::BuildingPlot);
+
+    harness.scroll(1.0);
+    harness.move_cursor_to(start);
+    harness.step(1);
+    let ghost = harness.ghost().unwrap();
+    assert_eq!((ghost.cell_idx, ghost.kind, ghost.cost), (start, EquipmentKind::Bend, BEND_COST));
+    assert_eq!(ghost.placement, Placement::Placeable);
+
+    // the bend laid where the drag starts is drawn for real, and the plot it can't go on next shows why
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - BEND_COST);
+    assert_eq!(harness.ghost(), None);
+    harness.move_cursor_to(plot);
+    harness.step(1);
+    let ghost = harness.ghost().unwrap();
+    assert_eq!((ghost.cell_idx, ghost.kind), (plot, EquipmentKind::Bend));
+    assert_eq!(ghost.placement, Placement::ForbiddenTerrain);
+}
+
+
+#[test]
+fn the_selected_equipment_is_priced_for_the_terrain_under_the_cursor() {
+    let mut harness = Harness::in_game();
+    let road = CellId::new(4, 4);
+    harness.set_terrain(road, Terrain::Road);
+
+    harness.move_cursor_to(CellId::new(3, 4));
+    harness.step(1);
+    assert_eq!(harness.selected_equipment(), format!("Straight Pipe ({})", PIPE_COST));
+
+    // roads cost twice as much to dig up
+    harness.move_cursor_to(road);
+    harness.step(1);
+    assert_eq!(harness.selected_equipment(), format!("Straight Pipe ({})", 2 * PIPE_COST));
+}
+
+
+#[test]
+fn the_preview_turns_red_when_the_cell_is_taken_or_unaffordable() {
+    let mut harness = Harness::in_game();
+    let taken = CellId::new(4, 4);
+
+    harness.move_cursor_to(taken);
+    harness.click(MouseButton::Left);
+    harness.step_until(|harness| harness.budget() == START_BUDGET - PIPE_COST);
+    harness.click(MouseButton::Left);
+    harness.step(1);
+    assert_eq!(harness.ghost().map(|ghost| ghost.placement), Some(Placement::Occupied));
+
+    harness.set_budget(0);
+    harness.move_cursor_to(CellId::new(5, 4));
+    harness.step(1);
+    assert_eq!(harness.ghost().map(|ghost| ghost.placement), Some(Placement::Unaffordable));
+}
+
+
+#[test]
+fn the_preview_is_hidden_while_deleting() {
+    let mut harness = Harness::in_game();
+
+    harness.move_cursor_to(CellId::new(4, 4));
+    harness.click(MouseButton::Right);
+    harness.step(1);
+
+    assert_eq!(harness.ghost(), None);
+}
+
+
+#[test]
+fn moving_the_cursor_off_the_window_clears_the_hovered_cell() {
+    let mut harness = Harness::in_game();
+
+    harness.move_cursor_to(CellId::new(4, 4));
+    assert_eq!(harness.hovered_cell(), Some(CellId::new(4, 4)));
+
+    harness.move_cursor_off_window();
+    harness.step(1);
+
+    assert_eq!(harness.hovered_cell(), None);
+    assert_eq!(harness.ghost(), None);
+}